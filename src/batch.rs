@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::thread;
+
+use crate::{DemangleError, Demangler, Symbol};
+
+// symbols per thread below which spawning costs more than it saves
+const MIN_CHUNK: usize = 256;
+// symbols a stream reads ahead per thread
+const STREAM_CHUNK: usize = 4096;
+
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+impl Demangler {
+    // Demangles `symbols` on all cores, every thread reading this context.
+    // Results are in input order.
+    pub fn demangle_batch<'a>(
+        &self,
+        symbols: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<Result<Symbol<'a>, DemangleError>> {
+        self.demangle_slice(&symbols.into_iter().collect::<Vec<_>>())
+    }
+
+    // `demangle_batch` for iterators of unknown length: symbols are read a
+    // chunk at a time, and names no longer borrow from them.
+    pub fn demangle_stream<I>(&self, symbols: I) -> DemangleStream<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        DemangleStream {
+            demangler: Cow::Borrowed(self),
+            symbols: symbols.into_iter(),
+            ready: Vec::new().into_iter(),
+        }
+    }
+
+    fn demangle_slice<'a>(&self, symbols: &[&'a str]) -> Vec<Result<Symbol<'a>, DemangleError>> {
+        self.demangle_slice_with(symbols, threads())
+    }
+
+    fn demangle_slice_with<'a>(
+        &self,
+        symbols: &[&'a str],
+        threads: usize,
+    ) -> Vec<Result<Symbol<'a>, DemangleError>> {
+        map_chunks(symbols, threads, |x| self.try_demangle(x))
+    }
+}
+
+// `f` over `items` on up to `threads` threads, in input order. A panic in a
+// thread is raised again here.
+fn map_chunks<T: Sync, R: Send>(items: &[T], threads: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let chunk = items.len().div_ceil(threads).max(MIN_CHUNK);
+    if chunk >= items.len() {
+        return items.iter().map(f).collect();
+    }
+
+    let f = &f;
+    thread::scope(|scope| {
+        let workers = items
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+pub struct DemangleStream<'d, I> {
+    demangler: Cow<'d, Demangler>,
+    symbols: I,
+    ready: std::vec::IntoIter<Result<Symbol<'static>, DemangleError>>,
+}
+
+impl<I> Iterator for DemangleStream<'_, I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = Result<Symbol<'static>, DemangleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(result) = self.ready.next() {
+            return Some(result);
+        }
+
+        let chunk = self
+            .symbols
+            .by_ref()
+            .take(STREAM_CHUNK * threads())
+            .collect::<Vec<_>>();
+        let symbols = chunk.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        self.ready = self
+            .demangler
+            .demangle_slice(&symbols)
+            .into_iter()
+            .map(|result| result.map(Symbol::into_owned))
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.ready.next()
+    }
+}
+
+pub fn demangle_batch<'a>(
+    symbols: impl IntoIterator<Item = &'a str>,
+) -> Vec<Result<Symbol<'a>, DemangleError>> {
+    Demangler::default().demangle_batch(symbols)
+}
+
+pub fn demangle_stream<I>(symbols: I) -> DemangleStream<'static, I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    DemangleStream {
+        demangler: Cow::Owned(Demangler::default()),
+        symbols: symbols.into_iter(),
+        ready: Vec::new().into_iter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_demangle;
+
+    // the machine running the tests may have a single core
+    const THREADS: usize = 4;
+
+    #[test]
+    fn threads_keep_order() {
+        let corpus = (0..20_000)
+            .map(|i| match i % 3 {
+                0 => format!("f{i}__3BarFi"),
+                1 => "foo__3BarFv_x".to_string(),
+                _ => "main".to_string(),
+            })
+            .collect::<Vec<_>>();
+        let symbols = corpus.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let results = Demangler::default().demangle_slice_with(&symbols, THREADS);
+        assert_eq!(results.len(), symbols.len());
+        for (symbol, result) in symbols.iter().zip(results) {
+            assert_eq!(result.map(|x| x.name), try_demangle(symbol).map(|x| x.name));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "item 1500")]
+    fn threads_raise_panics() {
+        let items = (0..2_000).collect::<Vec<_>>();
+        map_chunks(&items, THREADS, |&x| match x {
+            1500 => panic!("item {x}"),
+            x => x,
+        });
+    }
+}
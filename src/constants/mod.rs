@@ -2,6 +2,6 @@ mod base_types;
 mod name_modifiers;
 mod special_names;
 
-pub use base_types::get_base_types;
+pub use base_types::{base_type_code, base_type_name};
 pub use name_modifiers::*;
-pub use special_names::get_special_names;
+pub use special_names::{special_name, SPECIAL_NAMES};
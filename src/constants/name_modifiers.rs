@@ -1,30 +1,52 @@
-use once_cell::sync::OnceCell;
-use std::collections::HashMap;
+// One table for `Modifier`, its code and its spelling, so every modifier
+// can be mangled back.
+macro_rules! modifiers {
+    ($($code:literal => $modifier:ident($spelling:literal),)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Modifier {
+            $($modifier,)*
+        }
 
-#[derive(Clone, Debug)]
-pub enum Modifier {
-    OnPrefix(String),
-    OnSuffix(String),
-}
+        pub fn name_modifier(code: char) -> Option<Modifier> {
+            match code {
+                $($code => Some(Modifier::$modifier),)*
+                _ => None,
+            }
+        }
+
+        impl Modifier {
+            pub fn code(self) -> char {
+                match self {
+                    $(Modifier::$modifier => $code,)*
+                }
+            }
 
-static MODIFIERS: OnceCell<HashMap<char, Modifier>> = OnceCell::new();
-pub fn get_name_modifiers() -> &'static HashMap<char, Modifier> {
-    let name_modifiers = MODIFIERS.get();
-    if name_modifiers.is_none() {
-        let mut name_modifiers = HashMap::new();
+            pub fn spelling(self) -> &'static str {
+                match self {
+                    $(Modifier::$modifier => $spelling,)*
+                }
+            }
+        }
+    };
+}
 
-        name_modifiers.insert('U', Modifier::OnPrefix("unsigned".to_string()));
-        name_modifiers.insert('S', Modifier::OnPrefix("signed".to_string()));
-        name_modifiers.insert('J', Modifier::OnPrefix("__complex".to_string()));
-        name_modifiers.insert('M', Modifier::OnPrefix("[M]".to_string()));
-        name_modifiers.insert('P', Modifier::OnSuffix("*".to_string()));
-        name_modifiers.insert('R', Modifier::OnSuffix("&".to_string()));
-        name_modifiers.insert('C', Modifier::OnSuffix("const".to_string()));
-        name_modifiers.insert('V', Modifier::OnSuffix("volatile".to_string()));
-        name_modifiers.insert('u', Modifier::OnSuffix("restrict".to_string()));
+modifiers! {
+    'U' => Unsigned("unsigned"),
+    'S' => Signed("signed"),
+    'J' => Complex("__complex"),
+    'P' => Pointer("*"),
+    'R' => Reference("&"),
+    'C' => Const("const"),
+    'V' => Volatile("volatile"),
+    'u' => Restrict("restrict"),
+}
 
-        MODIFIERS.set(name_modifiers).unwrap();
-        return get_name_modifiers();
+impl Modifier {
+    // `unsigned` and friends are part of the type, not of its declarator
+    pub fn is_prefix(self) -> bool {
+        matches!(
+            self,
+            Modifier::Unsigned | Modifier::Signed | Modifier::Complex
+        )
     }
-    name_modifiers.unwrap()
 }
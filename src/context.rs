@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use crate::{candidates_symbol, demangle_symbol, DemangleError, Mode, Name, Score, Symbol};
+
+// Class and namespace names seen across a symbol table. `preprocess` uses
+// them to choose between the readings of an ambiguous symbol, e.g.
+// `x__9Bar__3BazFv` is `Baz::x__9Bar` once `Baz` is known.
+#[derive(Debug, Clone, Default)]
+pub struct Demangler {
+    mode: Mode,
+    known: HashSet<String>,
+}
+
+impl Demangler {
+    pub fn new(mode: Mode) -> Self {
+        Demangler {
+            mode,
+            known: HashSet::new(),
+        }
+    }
+
+    pub fn add_known(&mut self, name: &str) {
+        self.known.insert(name.to_string());
+    }
+
+    pub fn is_known(&self, name: &str) -> bool {
+        self.known.contains(name)
+    }
+
+    // First pass over a symbol table: remembers the scopes of every symbol
+    // with a single reading.
+    pub fn learn<'a>(&mut self, symbols: impl IntoIterator<Item = &'a str>) {
+        for symbol in symbols {
+            let name = match demangle_symbol(symbol, Mode::Strict, &self.known) {
+                Ok(symbol) if !symbol.decision.is_ambiguous() => symbol.name,
+                _ => continue,
+            };
+            let mut names = vec![];
+            scope_names(&name, &mut names);
+            self.known.extend(names.into_iter().map(str::to_string));
+        }
+    }
+
+    pub fn try_demangle<'a>(&self, input: &'a str) -> Result<Symbol<'a>, DemangleError> {
+        demangle_symbol(input, self.mode, &self.known)
+    }
+
+    // `demangle_candidates`, ranked with the known names
+    pub fn demangle_candidates<'a>(&self, input: &'a str) -> Vec<(Name<'a>, Score)> {
+        candidates_symbol(input, &self.known)
+    }
+}
+
+// Identifiers naming classes or namespaces: all but the symbol's own name,
+// e.g. `Bar` and `Baz` in `Bar::foo(Baz)`.
+fn scope_names<'a>(name: &'a Name, names: &mut Vec<&'a str>) {
+    match name {
+        Name::Thunk { target, .. } => scope_names(target, names),
+        Name::WithReturnValue(base, ret) => {
+            scope_names(base, names);
+            type_names(ret, names);
+        }
+        Name::WithArguments(base, args, _) | Name::Template(base, args) => {
+            scope_names(base, names);
+            args.iter().for_each(|x| type_names(x, names));
+        }
+        Name::InName(leaf, parent) => {
+            scope_names(leaf, names);
+            type_names(parent, names);
+        }
+        Name::Identifier(_) | Name::SpecialName(_) => {}
+        name => type_names(name, names),
+    }
+}
+
+fn type_names<'a>(name: &'a Name, names: &mut Vec<&'a str>) {
+    match name {
+        Name::Identifier(x) => names.push(x),
+        name => name.for_each_child(&mut |x| type_names(x, names)),
+    }
+}
+
+// Ranks a reading by how many of its scopes are known. A scope that is not
+// known is no evidence against it, so ties keep the first split first.
+pub(crate) fn evidence(name: &Name, known: &HashSet<String>) -> usize {
+    let mut names = vec![];
+    scope_names(name, &mut names);
+    names.iter().filter(|x| known.contains(**x)).count()
+}
@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use crate::{extract_string, number, parse_thunk, try_demangle, DemangleError, Name, Stage};
+
+// __CPR<len>__<data>: `J<offset>J` in <data> repeats the length-prefixed
+// string found at <offset> of the output so far, `JJ` is a literal `J`.
+// A thunk prefix is kept in front of the header, and input without the
+// header is returned as is.
+pub fn decompress_cpr(input: &str) -> Result<String, DemangleError> {
+    if let Ok((Some(_), target)) = parse_thunk(input) {
+        let prefix = &input[..input.len() - target.len()];
+        return Ok(format!("{prefix}{}", decompress_in(input, target)?));
+    }
+    decompress(input).map(Cow::into_owned)
+}
+
+// `decompress` of `target`, the end of `input`, with errors at their offset
+// in `input`
+pub(crate) fn decompress_in<'a>(
+    input: &str,
+    target: &'a str,
+) -> Result<Cow<'a, str>, DemangleError> {
+    decompress(target).map_err(|e| DemangleError::new(e.stage, input, &e.remaining))
+}
+
+// borrows `input` unless there is something to expand
+pub(crate) fn decompress(input: &str) -> Result<Cow<'_, str>, DemangleError> {
+    let error = |remaining: &str| DemangleError::new(Stage::Decompress, input, remaining);
+
+    let header = match input.strip_prefix("__CPR") {
+        Some(header) => header,
+        None => return Ok(Cow::Borrowed(input)),
+    };
+
+    let (data, decompressed_length) = number(header).map_err(|_| error(header))?;
+    let raw_data = data.strip_prefix("__").ok_or_else(|| error(data))?;
+
+    // the header is not trusted until the output matches it
+    let mut decompressed = String::with_capacity(decompressed_length.min(raw_data.len()));
+    let mut rest = raw_data;
+    for (i, token) in raw_data.split('J').enumerate() {
+        let at = rest;
+        rest = rest.get(token.len() + 1..).unwrap_or("");
+
+        if i % 2 == 0 {
+            decompressed += token;
+            continue;
+        }
+        // odd tokens must be closed by another `J`
+        if at.len() == token.len() {
+            return Err(error(at));
+        }
+        if token.is_empty() {
+            decompressed += "J";
+            continue;
+        }
+
+        let offset = token.parse::<usize>().map_err(|_| error(at))?;
+        let referenced = decompressed
+            .get(offset..)
+            .and_then(|x| extract_string(x).ok())
+            .ok_or_else(|| error(at))?
+            .1;
+        decompressed += &(referenced.len().to_string() + referenced);
+        if decompressed.len() > decompressed_length {
+            return Err(error(at));
+        }
+    }
+
+    if decompressed.len() != decompressed_length {
+        return Err(error(header));
+    }
+
+    Ok(Cow::Owned(decompressed))
+}
+
+// Starts of the length-prefixed names the grammar reads in `expanded`,
+// found through the identifiers the parse borrows from it. The digits
+// before an identifier may also end a count (`T13Foo`), so the prefix is
+// the shortest one that spans it, or its class template arguments.
+fn name_positions(expanded: &str) -> HashSet<usize> {
+    fn collect(name: &Name, expanded: &str, positions: &mut HashSet<usize>) {
+        if let Name::Identifier(Cow::Borrowed(x)) | Name::SpecialName(Cow::Borrowed(x)) = name {
+            let start = (x.as_ptr() as usize).wrapping_sub(expanded.as_ptr() as usize);
+            if let Some(before) = expanded.get(..start) {
+                let after = &expanded[start + x.len()..];
+                let digits =
+                    before.len() - before.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+                let prefix = (1..=digits).find(|&d| match before[start - d..].parse::<usize>() {
+                    Ok(n) if n == x.len() => true,
+                    Ok(n) => n > x.len() && after.starts_with("__tm__"),
+                    Err(_) => false,
+                });
+                if let Some(d) = prefix {
+                    positions.insert(start - d);
+                }
+            }
+        }
+        name.for_each_child(&mut |x| collect(x, expanded, positions));
+    }
+
+    let mut positions = HashSet::new();
+    if let Ok(symbol) = try_demangle(expanded) {
+        collect(&symbol.name, expanded, &mut positions);
+    }
+    positions
+}
+
+// Inverse of `decompress_cpr`: every length-prefixed name seen before is
+// replaced by `J<offset>J` pointing at its first occurrence, whenever that
+// is shorter. A thunk prefix stays outside the compressed body, and symbols
+// the grammar cannot read are only escaped.
+//
+// The output decompresses back to the input, but is not checked against
+// the toolchain's own, whose back-reference choices may differ. It also
+// only compresses names past a length limit, which is not applied here:
+// the caller decides which symbols to compress, and any input is wrapped,
+// `main` as `__CPR4__main`. Input already compressed is returned as is.
+pub fn compress_cpr(expanded: &str) -> String {
+    if let Ok((Some(_), target)) = parse_thunk(expanded) {
+        let prefix = &expanded[..expanded.len() - target.len()];
+        return format!("{prefix}{}", compress_cpr(target));
+    }
+    if expanded.starts_with("__CPR") {
+        return expanded.to_string();
+    }
+
+    let positions = name_positions(expanded);
+    let mut seen = HashMap::new();
+    let mut data = String::with_capacity(expanded.len());
+
+    let mut i = 0;
+    while i < expanded.len() {
+        let token = positions
+            .get(&i)
+            .and_then(|_| extract_string(&expanded[i..]).ok())
+            .map(|(after, _)| &expanded[i..expanded.len() - after.len()]);
+        let token = match token {
+            Some(token) => token,
+            None => {
+                let c = expanded[i..].chars().next().unwrap();
+                match c {
+                    'J' => data += "JJ",
+                    c => data.push(c),
+                }
+                i += c.len_utf8();
+                continue;
+            }
+        };
+
+        match seen.get(token) {
+            Some(offset) if format!("J{offset}J").len() < token.len() => {
+                data += &format!("J{offset}J");
+            }
+            _ => {
+                seen.entry(token).or_insert(i);
+                data += &token.replace('J', "JJ");
+            }
+        }
+        i += token.len();
+    }
+
+    format!("__CPR{}__{}", expanded.len(), data)
+}
@@ -0,0 +1,497 @@
+use std::sync::Arc;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0, none_of},
+    combinator::{map, map_res, not, opt, peek, recognize, value, verify},
+    multi::{many0, many0_count, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, terminated},
+    IResult, Parser,
+};
+
+use crate::constants::{base_type_code, Modifier, SPECIAL_NAMES};
+use crate::{DemangleError, FunctionQualifiers, Name, Stage, TemplateValue};
+
+// A C++ declaration such as `nn::fs::OpenFile(const char*, int)`, read into
+// the same `Name` tree `demangle` produces.
+
+const KEYWORDS: &[&str] = &[
+    "const", "volatile", "static", "unsigned", "signed", "void", "bool", "char", "wchar_t",
+    "short", "int", "long", "float", "double", "operator", "new", "delete", "true", "false",
+    "template", "class", "struct", "typename",
+];
+
+const TYPE_WORDS: &[&str] = &[
+    "unsigned", "signed", "void", "bool", "char", "wchar_t", "short", "int", "long", "float",
+    "double",
+];
+
+// `*`, `const` etc. applied to a type, innermost first
+#[derive(Clone)]
+enum Op<'a> {
+    Pointer,
+    Reference,
+    Const,
+    Volatile,
+    Member(Name<'a>),
+    Array(Option<usize>), // `[]` has no bound, see `parameter`
+    Function(Vec<Name<'a>>, FunctionQualifiers),
+}
+
+fn modified(modifier: Modifier, ty: Name) -> Name {
+    Name::Modifier(modifier, Arc::new(ty))
+}
+
+fn apply<'a>(ty: Name<'a>, ops: Vec<Op<'a>>) -> Name<'a> {
+    ops.into_iter().fold(ty, |ty, op| match op {
+        Op::Pointer => modified(Modifier::Pointer, ty),
+        Op::Reference => modified(Modifier::Reference, ty),
+        Op::Const => modified(Modifier::Const, ty),
+        Op::Volatile => modified(Modifier::Volatile, ty),
+        Op::Member(class) => Name::PointerToMember(Arc::new(class), Arc::new(ty)),
+        Op::Array(Some(size)) => Name::SizedArray(size, Arc::new(ty)),
+        Op::Array(None) => modified(Modifier::Pointer, ty),
+        Op::Function(args, qualifiers) => Name::FunctionPointer(args, Arc::new(ty), qualifiers),
+    })
+}
+
+fn scope(mut path: Vec<Name>) -> Name {
+    match path.len() {
+        1 => path.remove(0),
+        _ => Name::Namespace(path),
+    }
+}
+
+fn class_name<'a>(name: &'a Name) -> Option<&'a str> {
+    match name {
+        Name::Identifier(x) => Some(x),
+        Name::Template(base, _) => class_name(base),
+        _ => None,
+    }
+}
+
+fn token<'a, O>(
+    parser: impl Parser<&'a str, O, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    preceded(multispace0, parser)
+}
+
+fn word(input: &str) -> IResult<&str, &str> {
+    token(recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0_count(alt((alphanumeric1, tag("_")))),
+    )))(input)
+}
+
+fn keyword<'a>(x: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    verify(word, move |y: &str| y == x)
+}
+
+fn identifier(input: &str) -> IResult<&str, &str> {
+    verify(word, |x: &str| !KEYWORDS.contains(&x))(input)
+}
+
+fn integer(input: &str) -> IResult<&str, i128> {
+    map_res(token(recognize(pair(opt(char('-')), digit1))), str::parse)(input)
+}
+
+fn number(input: &str) -> IResult<&str, usize> {
+    map_res(token(digit1), str::parse)(input)
+}
+
+fn value_argument<'a>(input: &'a str) -> IResult<&'a str, TemplateValue<'a>> {
+    let cast = |(ty, x): (Name<'a>, i128)| match ty {
+        Name::BaseType(_) => TemplateValue::Integer(Arc::new(ty), x),
+        ty => TemplateValue::Enum(Arc::new(ty), x),
+    };
+    alt((
+        value(TemplateValue::Bool(true), keyword("true")),
+        value(TemplateValue::Bool(false), keyword("false")),
+        map(integer, |x| {
+            TemplateValue::Integer(Arc::new(Name::BaseType('i')), x)
+        }),
+        map(
+            delimited(token(char('\'')), none_of("'\\"), char('\'')),
+            |x| TemplateValue::Char(Arc::new(Name::BaseType('c')), x as u32),
+        ),
+        map(preceded(token(char('&')), identifier), |x| {
+            TemplateValue::Address(x.into())
+        }),
+        map(
+            pair(
+                delimited(token(char('(')), type_id, token(char(')'))),
+                integer,
+            ),
+            cast,
+        ),
+    ))(input)
+}
+
+fn template_arguments(input: &str) -> IResult<&str, Vec<Name<'_>>> {
+    delimited(
+        token(char('<')),
+        separated_list1(
+            token(char(',')),
+            alt((map(value_argument, Name::ValueArgument), type_id)),
+        ),
+        token(char('>')),
+    )(input)
+}
+
+// `Vec<int>` in `Vec<int>::size`
+fn component(input: &str) -> IResult<&str, Name<'_>> {
+    map(
+        pair(identifier, opt(template_arguments)),
+        |(x, args)| match args {
+            Some(args) => Name::Template(Arc::new(Name::identifier_from_str(x)), args),
+            None => Name::identifier_from_str(x),
+        },
+    )(input)
+}
+
+fn qualified(input: &str) -> IResult<&str, Name<'_>> {
+    map(separated_list1(token(tag("::")), component), scope)(input)
+}
+
+// `unsigned long`, `const Foo` or `nn::fs::Path`
+fn specifiers(input: &str) -> IResult<&str, Name<'_>> {
+    let start = input;
+    let mut input = input;
+    let mut words = Vec::new();
+    let mut class = None;
+    let (mut is_const, mut is_volatile) = (false, false);
+
+    while let (rest, Some(x)) = opt(word)(input)? {
+        match x {
+            "const" => is_const = true,
+            "volatile" => is_volatile = true,
+            x if TYPE_WORDS.contains(&x) && class.is_none() => words.push(x),
+            _ if words.is_empty() && class.is_none() => {
+                let (rest, name) = qualified(input)?;
+                class = Some(name);
+                input = rest;
+                continue;
+            }
+            _ => break,
+        }
+        input = rest;
+    }
+
+    let ty = match class {
+        Some(class) => class,
+        None => match base_type(&words) {
+            Some(ty) => ty,
+            None => {
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    start,
+                    nom::error::ErrorKind::Verify,
+                )))
+            }
+        },
+    };
+    let ty = match is_volatile {
+        true => modified(Modifier::Volatile, ty),
+        false => ty,
+    };
+    let ty = match is_const {
+        true => modified(Modifier::Const, ty),
+        false => ty,
+    };
+    Ok((input, ty))
+}
+
+fn base_type(words: &[&str]) -> Option<Name<'static>> {
+    let sign = words.iter().find(|x| **x == "unsigned" || **x == "signed");
+    let sized = words.iter().any(|x| *x == "short" || *x == "long");
+
+    // `unsigned` alone is an int, `int` after a size is implied
+    let spelled = words
+        .iter()
+        .filter(|x| Some(*x) != sign && !(sized && **x == "int"))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let spelled = match spelled.as_str() {
+        "" if sign.is_some() => "int",
+        x => x,
+    };
+
+    let ty = Name::BaseType(base_type_code(spelled)?);
+    Some(match sign {
+        Some(&"unsigned") => modified(Modifier::Unsigned, ty),
+        Some(_) => modified(Modifier::Signed, ty),
+        None => ty,
+    })
+}
+
+fn function_qualifiers(input: &str) -> IResult<&str, FunctionQualifiers> {
+    map(
+        many0(alt((keyword("const"), keyword("volatile")))),
+        |x: Vec<&str>| FunctionQualifiers {
+            is_static: false,
+            static_marker: false,
+            is_const: x.contains(&"const"),
+            is_volatile: x.contains(&"volatile"),
+        },
+    )(input)
+}
+
+// Only the outermost array of a parameter may leave out its bound.
+fn bounded(ops: &[Op]) -> bool {
+    !ops.iter().any(|x| matches!(x, Op::Array(None)))
+}
+
+// A `const` or `volatile` on a parameter itself is not part of the
+// function type, and arrays and functions decay to pointers: `f(const int,
+// int[4], void(int))` is `f(int, int*, void (*)(int))`.
+fn parameter_type(ty: Name) -> Name {
+    match ty {
+        Name::Modifier(Modifier::Const | Modifier::Volatile, ty) => {
+            parameter_type(Arc::unwrap_or_clone(ty))
+        }
+        Name::SizedArray(_, element) => Name::Modifier(Modifier::Pointer, element),
+        ty @ Name::FunctionPointer(_, _, _) => modified(Modifier::Pointer, ty),
+        ty => ty,
+    }
+}
+
+fn parameter(input: &str) -> IResult<&str, Name<'_>> {
+    let start = input;
+    let (input, (ty, mut ops)) = pair(specifiers, declarator)(input)?;
+    let outermost = match ops.last() {
+        Some(Op::Array(None)) => ops.pop(),
+        _ => None,
+    };
+    if !bounded(&ops) {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            start,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    ops.extend(outermost);
+    Ok((input, parameter_type(apply(ty, ops))))
+}
+
+// `()` is written as `(void)`
+fn parameters(input: &str) -> IResult<&str, Vec<Name<'_>>> {
+    map(
+        delimited(
+            token(char('(')),
+            separated_list0(
+                token(char(',')),
+                alt((value(Name::BaseType('e'), token(tag("..."))), parameter)),
+            ),
+            token(char(')')),
+        ),
+        |args| match args.is_empty() {
+            true => vec![Name::BaseType('v')],
+            false => args,
+        },
+    )(input)
+}
+
+fn pointer_op(input: &str) -> IResult<&str, Op<'_>> {
+    alt((
+        value(Op::Pointer, token(char('*'))),
+        // `&&` is an rvalue reference, which has no encoding
+        value(Op::Reference, terminated(token(char('&')), not(char('&')))),
+        value(Op::Const, keyword("const")),
+        value(Op::Volatile, keyword("volatile")),
+        map(
+            terminated(
+                separated_list1(token(tag("::")), component),
+                pair(token(tag("::")), token(char('*'))),
+            ),
+            |path| Op::Member(scope(path)),
+        ),
+    ))(input)
+}
+
+// An abstract declarator, e.g. `(*)[4]` or `(Foo::*)(int) const`; a
+// parameter name in it is skipped.
+fn declarator(input: &str) -> IResult<&str, Vec<Op<'_>>> {
+    let (input, mut ops) = many0(pointer_op)(input)?;
+    let (input, inner) = opt(delimited(
+        token(char('(')),
+        verify(declarator, |x: &Vec<Op>| !x.is_empty()),
+        token(char(')')),
+    ))(input)?;
+    let input = match inner {
+        Some(_) => input,
+        None => opt(identifier)(input)?.0,
+    };
+    let (input, mut suffixes) = many0(suffix)(input)?;
+
+    // `[2][3]` is an array of 2 arrays of 3
+    suffixes.reverse();
+    ops.append(&mut suffixes);
+    ops.append(&mut inner.unwrap_or_default());
+    Ok((input, ops))
+}
+
+fn suffix(input: &str) -> IResult<&str, Op<'_>> {
+    alt((
+        map(
+            delimited(token(char('[')), opt(number), token(char(']'))),
+            Op::Array,
+        ),
+        map(
+            pair(parameters, function_qualifiers),
+            |(args, qualifiers)| Op::Function(args, qualifiers),
+        ),
+    ))(input)
+}
+
+// The declarator around the declared name, e.g. `(*f(char))(double)`; the
+// last of its ops is the function's own parameter list, if it has one.
+// Parentheses only group a pointer, so `Bar::f(Bar::Baz)` is a function.
+fn named_declarator(input: &str) -> IResult<&str, (Vec<Op<'_>>, Vec<Name<'_>>)> {
+    let (input, mut ops) = many0(pointer_op)(input)?;
+    let (input, (mut inner, path)) = alt((
+        delimited(
+            token(char('(')),
+            preceded(
+                peek(verify(pointer_op, |x| {
+                    !matches!(x, Op::Const | Op::Volatile)
+                })),
+                named_declarator,
+            ),
+            token(char(')')),
+        ),
+        map(function_name, |path| (vec![], path)),
+    ))(input)?;
+    let (input, mut suffixes) = many0(suffix)(input)?;
+
+    suffixes.reverse();
+    ops.append(&mut suffixes);
+    ops.append(&mut inner);
+    Ok((input, (ops, path)))
+}
+
+fn type_id(input: &str) -> IResult<&str, Name<'_>> {
+    map(
+        verify(pair(specifiers, declarator), |(_, ops)| bounded(ops)),
+        |(ty, ops)| apply(ty, ops),
+    )(input)
+}
+
+fn operator(input: &str) -> IResult<&str, Name<'_>> {
+    // the longest match, so `->*` is not read as `->`; `__amu` over its alias `__aml`
+    let symbol = SPECIAL_NAMES
+        .iter()
+        .filter_map(|(code, name)| Some((name.strip_prefix("operator")?.trim(), *code)))
+        .filter_map(|(symbol, code)| {
+            let (rest, _) = token(tag(symbol))(input).ok()?;
+            Some((rest, symbol.len(), code))
+        })
+        .max_by_key(|&(_, len, code)| (len, code));
+    if let Some((rest, _, code)) = symbol {
+        return Ok((rest, Name::SpecialName(code.into())));
+    }
+    map(pair(specifiers, many0(pointer_op)), |(ty, ops)| {
+        Name::Conversion(Arc::new(apply(ty, ops)))
+    })(input)
+}
+
+// `~X` only names the destructor of a class `X`
+fn function_name(input: &str) -> IResult<&str, Vec<Name<'_>>> {
+    let (input, mut path) = many0(terminated(component, token(tag("::"))))(input)?;
+    let class = path.last().and_then(class_name);
+    let (input, leaf) = alt((
+        preceded(keyword("operator"), operator),
+        map(
+            verify(preceded(token(char('~')), identifier), |x: &str| {
+                Some(x) == class
+            }),
+            |_| Name::SpecialName("__dt".into()),
+        ),
+        component,
+    ))(input)?;
+    path.push(leaf);
+    Ok((input, path))
+}
+
+fn declaration(input: &str) -> IResult<&str, Name<'_>> {
+    let start = input;
+    let (input, is_static) = opt(keyword("static"))(input)?;
+    // without a type, e.g. `Foo::Foo()`, there is nothing to point to
+    let (input, (ty, (mut ops, mut path))) = alt((
+        pair(
+            map(specifiers, Some),
+            verify(named_declarator, |(ops, _): &(Vec<Op>, _)| bounded(ops)),
+        ),
+        pair(
+            |input| Ok((input, None)),
+            verify(named_declarator, |(ops, _): &(Vec<Op>, _)| {
+                matches!(ops.as_slice(), [] | [Op::Function(_, _)])
+            }),
+        ),
+    ))(input)?;
+
+    let function = match ops.pop() {
+        Some(Op::Function(args, qualifiers)) => Some((args, qualifiers)),
+        op => {
+            ops.extend(op);
+            None
+        }
+    };
+    let ret = ty.map(|ty| apply(ty, ops));
+
+    let leaf = path.pop().unwrap();
+    // a file-static function is no static member
+    let is_member = !path.is_empty();
+    let is_static = is_static.is_some() && is_member;
+    // `Foo::Foo`
+    let leaf = match (&leaf, path.last().and_then(class_name)) {
+        (Name::Identifier(x), Some(class)) if x == class => Name::SpecialName("__ct".into()),
+        _ => leaf,
+    };
+    // only function templates keep their return type in the symbol
+    let is_template = matches!(leaf, Name::Template(_, _));
+    let name = match path.is_empty() {
+        true => leaf,
+        false => Name::InName(Arc::new(leaf), Arc::new(scope(path))),
+    };
+
+    let name = match function {
+        // `const` and `volatile` qualify `this`, which only members have
+        Some((_, qualifiers))
+            if (qualifiers.is_const || qualifiers.is_volatile) && (is_static || !is_member) =>
+        {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                start,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+        Some((args, qualifiers)) => {
+            let qualifiers = FunctionQualifiers {
+                is_static,
+                ..qualifiers
+            };
+            let function = Name::WithArguments(Arc::new(name), args, qualifiers);
+            match ret {
+                Some(ret) if is_template => {
+                    Name::WithReturnValue(Arc::new(function), Arc::new(ret))
+                }
+                _ => function,
+            }
+        }
+        None => name,
+    };
+    Ok((input, name))
+}
+
+pub fn parse_declaration(input: &str) -> Result<Name<'_>, DemangleError> {
+    let error = |remaining: &str| DemangleError::new(Stage::Declaration, input, remaining);
+    match terminated(declaration, multispace0)(input) {
+        Ok(("", name)) => Ok(name),
+        Ok((remaining, _)) => Err(error(remaining)),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(error(e.input)),
+        Err(nom::Err::Incomplete(_)) => Err(error(input)),
+    }
+}
+
+pub fn mangle_declaration(input: &str) -> Result<String, DemangleError> {
+    parse_declaration(input).map(|name| name.mangle())
+}
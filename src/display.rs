@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::constants::{self, base_type_name, Modifier};
+use crate::{FunctionQualifiers, Name, TemplateValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DemangleOptions {
+    pub no_params: bool,        // foo(int) -> foo
+    pub no_return_type: bool,   // int foo<int>() -> foo<int>()
+    pub no_template_args: bool, // foo<int> -> foo
+    pub no_namespace: bool,     // a::b::foo -> foo
+    pub no_cv_qualifiers: bool, // const char* -> char*
+}
+
+impl DemangleOptions {
+    pub fn name_only() -> Self {
+        DemangleOptions {
+            no_params: true,
+            no_return_type: true,
+            no_template_args: true,
+            no_namespace: true,
+            no_cv_qualifiers: true,
+        }
+    }
+}
+
+pub struct DisplayWith<'a> {
+    name: &'a Name<'a>,
+    options: &'a DemangleOptions,
+}
+
+impl Name<'_> {
+    pub fn display_with<'a>(&'a self, options: &'a DemangleOptions) -> DisplayWith<'a> {
+        DisplayWith {
+            name: self,
+            options,
+        }
+    }
+}
+
+// `#` in a special name stands for the unqualified name of the enclosing
+// class, e.g. `__ct__12Vec__tm__2_iFv` -> `Vec<int>::Vec()`.
+fn special_name(code: &str, class: Option<&Name>, options: &DemangleOptions) -> String {
+    match (constants::special_name(code), class.and_then(unqualified)) {
+        (Some(name), Some(class)) => {
+            let class = class.display_with(options).to_string();
+            name.replace('#', &class)
+        }
+        (Some(name), None) if !name.contains('#') => name.to_string(),
+        _ => code.to_string(),
+    }
+}
+
+fn unqualified<'a>(name: &'a Name<'a>) -> Option<&'a Name<'a>> {
+    match name {
+        Name::Identifier(_) => Some(name),
+        Name::Template(base, _) => unqualified(base),
+        Name::Namespace(path) => path.last().and_then(unqualified),
+        Name::InName(leaf, _) => unqualified(leaf),
+        _ => None,
+    }
+}
+
+fn cv_suffix(qualifiers: &FunctionQualifiers, options: &DemangleOptions) -> &'static str {
+    match (qualifiers.is_const, qualifiers.is_volatile) {
+        _ if options.no_cv_qualifiers => "",
+        (true, true) => " const volatile",
+        (true, false) => " const",
+        (false, true) => " volatile",
+        (false, false) => "",
+    }
+}
+
+// a template parameter is written as the type argument it stands for
+fn resolved<'a>(name: &'a Name<'a>) -> &'a Name<'a> {
+    match name {
+        Name::TemplateParam {
+            resolved: Some(x), ..
+        } => resolved(x),
+        name => name,
+    }
+}
+
+fn is_indirect(name: &Name) -> bool {
+    matches!(
+        resolved(name),
+        Name::Modifier(Modifier::Pointer | Modifier::Reference, _) | Name::PointerToMember(_, _)
+    )
+}
+
+fn join(names: &[Name], separator: &str, options: &DemangleOptions) -> String {
+    names
+        .iter()
+        .map(|x| x.display_with(options).to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+// `(void)` is written as `()`
+fn params(args: &[Name], options: &DemangleOptions) -> String {
+    match args {
+        [Name::BaseType('v')] => String::new(),
+        _ => join(args, ", ", options),
+    }
+}
+
+// `name(args) const`, without `static`
+fn function(
+    name: &Name,
+    args: &[Name],
+    qualifiers: &FunctionQualifiers,
+    options: &DemangleOptions,
+) -> String {
+    format!(
+        "{}({}){}",
+        name.display_with(options),
+        params(args, options),
+        cv_suffix(qualifiers, options)
+    )
+}
+
+// A function type with the cv applied to it, which has nowhere to go but
+// after the parameters: `void (*)() const`.
+fn function_type<'b>(
+    ty: &'b Name<'b>,
+) -> Option<(&'b [Name<'b>], &'b Name<'b>, FunctionQualifiers)> {
+    match resolved(ty) {
+        Name::Modifier(modifier @ (Modifier::Const | Modifier::Volatile), x) => {
+            let (args, ret, mut qualifiers) = function_type(x)?;
+            qualifiers.is_const |= *modifier == Modifier::Const;
+            qualifiers.is_volatile |= *modifier == Modifier::Volatile;
+            Some((args, ret, qualifiers))
+        }
+        Name::FunctionPointer(args, ret, qualifiers) => Some((args, ret, *qualifiers)),
+        _ => None,
+    }
+}
+
+// Writes `ty` around `inner`, the declarator built so far, e.g. `int` around
+// `(*)[4]`. Pointers to arrays and functions get parenthesized on the way.
+fn declarator(ty: &Name, inner: String, options: &DemangleOptions) -> String {
+    // a cv-qualified array or function is still one: `const int (*)[4]`
+    fn is_grouped(pointee: &Name) -> bool {
+        match resolved(pointee) {
+            Name::Modifier(Modifier::Const | Modifier::Volatile, x) => is_grouped(x),
+            x => matches!(x, Name::SizedArray(_, _) | Name::FunctionPointer(_, _, _)),
+        }
+    }
+    let group = |inner: String, pointee: &Name| match is_grouped(pointee) {
+        true => format!("({inner})"),
+        false => inner,
+    };
+
+    if let Some((args, ret, qualifiers)) = function_type(ty) {
+        let inner = format!(
+            "{inner}({}){}",
+            params(args, options),
+            cv_suffix(&qualifiers, options)
+        );
+        return declarator(ret, inner, options);
+    }
+
+    match resolved(ty) {
+        // `char* name`, but `char**` and `int (*f(char))(double)`
+        Name::Modifier(modifier @ (Modifier::Pointer | Modifier::Reference), pointee) => {
+            let s = modifier.spelling();
+            let inner = match inner.chars().next() {
+                Some(c) if !is_grouped(pointee) && (c.is_alphanumeric() || c == '_') => {
+                    format!("{s} {inner}")
+                }
+                _ => format!("{s}{inner}"),
+            };
+            declarator(pointee, group(inner, pointee), options)
+        }
+        Name::Modifier(modifier, qualified)
+            if !modifier.is_prefix() && options.no_cv_qualifiers =>
+        {
+            declarator(qualified, inner, options)
+        }
+        // `char* const`, `char* const*`, but `const char*`
+        Name::Modifier(modifier, qualified) if !modifier.is_prefix() && is_indirect(qualified) => {
+            let s = modifier.spelling();
+            let inner = match inner.chars().next() {
+                None => format!(" {s}"),
+                Some('*' | '&') => format!(" {s}{inner}"),
+                Some(_) => format!(" {s} {inner}"),
+            };
+            declarator(qualified, inner, options)
+        }
+        Name::Modifier(modifier, ty) => {
+            format!("{} {}", modifier.spelling(), declarator(ty, inner, options))
+        }
+        Name::SizedArray(size, element) => declarator(element, format!("{inner}[{size}]"), options),
+        Name::PointerToMember(class, member) => {
+            let inner = format!("{}::*{inner}", class.display_with(options));
+            declarator(member, group(inner, member), options)
+        }
+        ty => {
+            let ty = ty.display_with(options);
+            match inner.chars().next() {
+                None => ty.to_string(),
+                Some('*' | '&' | '[' | ' ') => format!("{ty}{inner}"),
+                Some(_) => format!("{ty} {inner}"),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Name<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_with(&DemangleOptions::default()).fmt(f)
+    }
+}
+
+impl fmt::Display for DisplayWith<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = self.options;
+        match self.name {
+            Name::Identifier(x) => write!(f, "{x}"),
+            Name::SpecialName(x) => write!(f, "{}", special_name(x, None, options)),
+            Name::Conversion(ty) => write!(f, "operator {}", ty.display_with(options)),
+            Name::BaseType(x) => write!(f, "{}", base_type_name(*x).unwrap_or_default()),
+            Name::WithArguments(base, _, _) if options.no_params => {
+                write!(f, "{}", base.display_with(options))
+            }
+            Name::WithArguments(base, args, qualifiers) => {
+                if qualifiers.is_static {
+                    write!(f, "static ")?;
+                }
+                write!(f, "{}", function(base, args, qualifiers, options))
+            }
+            Name::Template(base, _) if options.no_template_args => {
+                write!(f, "{}", base.display_with(options))
+            }
+            Name::Template(base, args) => {
+                write!(
+                    f,
+                    "{}<{}>",
+                    base.display_with(options),
+                    join(args, ", ", options)
+                )
+            }
+            Name::Modifier(_, _)
+            | Name::SizedArray(_, _)
+            | Name::FunctionPointer(_, _, _)
+            | Name::PointerToMember(_, _) => {
+                write!(f, "{}", declarator(self.name, String::new(), options))
+            }
+            Name::Namespace(x) if options.no_namespace => match x.last() {
+                Some(last) => write!(f, "{}", last.display_with(options)),
+                None => Ok(()),
+            },
+            Name::Namespace(x) => write!(f, "{}", join(x, "::", options)),
+            Name::InName(leaf, parent) => {
+                let leaf = match &**leaf {
+                    Name::SpecialName(code) => special_name(code, Some(parent), options),
+                    leaf => leaf.display_with(options).to_string(),
+                };
+                match options.no_namespace {
+                    true => write!(f, "{leaf}"),
+                    false => write!(f, "{}::{leaf}", parent.display_with(options)),
+                }
+            }
+            Name::WithReturnValue(base, _) if options.no_return_type => {
+                write!(f, "{}", base.display_with(options))
+            }
+            // without its parameters the name can't sit inside the return
+            // type's declarator: `int (*)(double) Foo::bar<int>`
+            Name::WithReturnValue(base, ret) if options.no_params => {
+                let ret = ret.display_with(options);
+                write!(f, "{ret} {}", base.display_with(options))
+            }
+            // `static` leads the declaration, ahead of the return type
+            Name::WithReturnValue(base, ret) => {
+                let base = match &**base {
+                    Name::WithArguments(base, args, qualifiers) if !options.no_params => {
+                        if qualifiers.is_static {
+                            write!(f, "static ")?;
+                        }
+                        function(base, args, qualifiers, options)
+                    }
+                    base => base.display_with(options).to_string(),
+                };
+                write!(f, "{}", declarator(ret, base, options))
+            }
+            Name::ValueArgument(value) => match value {
+                TemplateValue::Integer(_, x) => write!(f, "{x}"),
+                TemplateValue::Bool(x) => write!(f, "{x}"),
+                TemplateValue::Char(_, x) => match char::from_u32(*x) {
+                    Some(c @ ('\'' | '\\')) => write!(f, "'\\{c}'"),
+                    Some('\n') => write!(f, "'\\n'"),
+                    Some('\t') => write!(f, "'\\t'"),
+                    Some('\0') => write!(f, "'\\0'"),
+                    Some(c) if c.is_ascii_graphic() || c == ' ' => write!(f, "'{c}'"),
+                    _ => write!(f, "'\\x{x:x}'"),
+                },
+                TemplateValue::Enum(ty, x) => write!(f, "({}){x}", ty.display_with(options)),
+                TemplateValue::Address(x) => write!(f, "&{x}"),
+                TemplateValue::Other(ty, x) => write!(f, "({}){x}", ty.display_with(options)),
+            },
+            Name::Thunk { adjustment, target } => {
+                let target = target.display_with(options);
+                match adjustment {
+                    x if *x < 0 => write!(f, "[thunk]: {target} (this-={})", x.unsigned_abs()),
+                    x => write!(f, "[thunk]: {target} (this+={x})"),
+                }
+            }
+            Name::TemplateParam {
+                resolved: Some(x), ..
+            } => write!(f, "{}", x.display_with(options)),
+            Name::TemplateParam {
+                index, level: 1, ..
+            } => write!(f, "T{index}"),
+            Name::TemplateParam { index, level, .. } => write!(f, "T{index}_{level}"),
+            Name::NameRef(x) => write!(f, "<NameRef {}>", x),
+            Name::NameRepeat(x, y) => write!(f, "<NameRepeat {} times of {}>", y, x),
+        }
+    }
+}
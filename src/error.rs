@@ -0,0 +1,56 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Thunk,         // __ghs_thunk__0x<offset>__
+    Decompress,    // __CPR<len>__<data>
+    Preprocess,    // splitting the leading identifier
+    Grammar,       // read_function
+    TrailingInput, // Mode::Strict
+    Declaration,   // parse_declaration
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Thunk => write!(f, "thunk prefix"),
+            Self::Decompress => write!(f, "__CPR decompression"),
+            Self::Preprocess => write!(f, "identifier splitting"),
+            Self::Grammar => write!(f, "grammar"),
+            Self::TrailingInput => write!(f, "full consumption check"),
+            Self::Declaration => write!(f, "declaration parsing"),
+        }
+    }
+}
+
+// `offset` is a byte offset into the input of the failing stage: the raw
+// symbol for `Thunk`/`Decompress`, the declaration for `Declaration`, the
+// expanded symbol for the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DemangleError {
+    pub stage: Stage,
+    pub offset: usize,
+    pub remaining: String,
+}
+
+impl DemangleError {
+    pub(crate) fn new(stage: Stage, input: &str, remaining: &str) -> Self {
+        DemangleError {
+            stage,
+            offset: input.len() - remaining.len(),
+            remaining: remaining.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DemangleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} failed at offset {}: {:?}",
+            self.stage, self.offset, self.remaining
+        )
+    }
+}
+
+impl std::error::Error for DemangleError {}
@@ -1,124 +1,424 @@
-use std::fs;
-
-use constants::{get_base_types, get_name_modifiers, Modifier};
+use constants::{base_type_name, name_modifier, special_name};
+use context::evidence;
 use nom::{
     branch::{alt, permutation},
     bytes::complete::{tag, take},
-    character::complete::{digit1, one_of},
-    combinator::opt,
-    multi::{count, many0},
-    sequence::{delimited, preceded, terminated},
+    character::complete::{anychar, digit1, one_of},
+    combinator::{map, map_opt, map_res, opt, verify},
+    multi::count,
+    sequence::{delimited, preceded, terminated, tuple},
     Parser,
 };
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
 
+mod batch;
 mod constants;
+mod context;
+mod cpr;
+mod declaration;
+mod display;
+mod error;
+mod mangle;
+
+pub use batch::{demangle_batch, demangle_stream, DemangleStream};
+pub use constants::Modifier;
+pub use context::Demangler;
+pub use cpr::{compress_cpr, decompress_cpr};
+pub use declaration::{mangle_declaration, parse_declaration};
+pub use display::{DemangleOptions, DisplayWith};
+pub use error::{DemangleError, Stage};
+
+// Identifiers borrow from the symbol where they can, and back-references
+// share the subtree they repeat.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Name<'a> {
+    Identifier(Cow<'a, str>),                                        // <String>
+    SpecialName(Cow<'a, str>), // __ct, __dt, __pl, ... (mangled form)
+    Conversion(Arc<Name<'a>>), // __op<Name>
+    BaseType(char),            // constants::base_type_name
+    WithArguments(Arc<Name<'a>>, Vec<Name<'a>>, FunctionQualifiers), // <Name>[S][C][V]F<Names>
+    Template(Arc<Name<'a>>, Vec<Name<'a>>), // __tm__<UnderString=Names>
+    Modifier(Modifier, Arc<Name<'a>>), // <Modifier><Name>
+    Namespace(Vec<Name<'a>>),  // Q<n>_[String; n]
+    InName(Arc<Name<'a>>, Arc<Name<'a>>), // <Name>__<Name>
+    WithReturnValue(Arc<Name<'a>>, Arc<Name<'a>>), // <Name>_<Name>
+    FunctionPointer(Vec<Name<'a>>, Arc<Name<'a>>, FunctionQualifiers), // [C][V]F<Names>_<Name>
+    PointerToMember(Arc<Name<'a>>, Arc<Name<'a>>), // M<Name -> class><Name -> member>
+    ValueArgument(TemplateValue<'a>), // X<String> | X<Name>L_<UnderString>
+    SizedArray(usize, Arc<Name<'a>>), // A[integer -> size]_[<Name> -> type]
+    TemplateParam {
+        index: usize,
+        level: usize,
+        resolved: Option<Arc<Name<'a>>>, // the type argument it stands for
+    }, // Z<index>[_<level>]Z
+    Thunk {
+        adjustment: i64,
+        target: Arc<Name<'a>>,
+    }, // __ghs_thunk__0x<hex>__<Name>
+    NameRef(usize),
+    NameRepeat(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue<'a> {
+    Integer(Arc<Name<'a>>, i128), // int, long, unsigned short, ...
+    Bool(bool),
+    Char(Arc<Name<'a>>, u32), // char, wchar_t, ... (code point)
+    Enum(Arc<Name<'a>>, i128),
+    Address(Cow<'a, str>), // &<String>
+    Other(Arc<Name<'a>>, Cow<'a, str>),
+}
+
+#[derive(Debug, Clone, Copy, Eq, Default)]
+pub struct FunctionQualifiers {
+    pub is_static: bool,     // __S / SF: no implicit `this`
+    pub static_marker: bool, // `is_static` spelled `__S` ahead of the list, not `SF`
+    pub is_const: bool,      // CF: `this` is const
+    pub is_volatile: bool,   // VF: `this` is volatile
+}
+
+// `static_marker` is only how the symbol spelled `is_static`, which only
+// `mangle` reads
+impl PartialEq for FunctionQualifiers {
+    fn eq(&self, other: &Self) -> bool {
+        (self.is_static, self.is_const, self.is_volatile)
+            == (other.is_static, other.is_const, other.is_volatile)
+    }
+}
 
 #[derive(Debug, Clone)]
-pub enum Name {
-    Identifier(String),                    // <String>
-    BaseType(char),                        // {base_types::get_base_types().keys |> one_of}
-    WithArguments(Box<Name>, Vec<Name>),   // <Name>[C][S]F<Names>
-    Template(Box<Name>, Vec<Name>),        // __tm__<UnderString=Names>
-    Modifier(Modifier, Box<Name>),         // <Modifier><Name>
-    Namespace(Vec<Name>),                  // Q<n>_[String; n]
-    InName(Box<Name>, Box<Name>),          // <Name>__<Name>
-    WithReturnValue(Box<Name>, Box<Name>), // <Name>_<Name>
-    FunctionPointer(Vec<Name>, Box<Name>), // F<Names>_<Name>
-    ValueArgument(Box<Name>, String),
-    /*
-        X[
-            (<String> -> value, "typename" -> type) |
-            (<Name> -> type, L_<UnderString> -> value)
-        ]
-    */
-    SizedArray(usize, Box<Name>), // A[integer -> size]_[<Name> -> type]
-    Names_Ref(usize),
-    Names_Multi(usize, usize),
-}
-
-impl std::fmt::Display for Name {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+pub struct Symbol<'a> {
+    pub name: Name<'a>,
+    pub remaining: String, // unparsed tail, always empty in `Mode::Strict`
+    pub decision: PreprocessDecision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreprocessRule {
+    Whole,         // no `__` boundary, e.g. `main`
+    Boundary,      // first `__` boundary after which the grammar consumes everything
+    KnownNames,    // a later such boundary whose scopes a `Demangler` knows
+    LongestPrefix, // Mode::Lenient: no boundary does, the one parsing furthest
+}
+
+// `chosen` is the length of the leading identifier; `candidates` holds every
+// split point yielding a distinct full parse, so more than one means the
+// symbol is ambiguous.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessDecision {
+    pub rule: PreprocessRule,
+    pub candidates: Vec<usize>,
+    pub chosen: usize,
+}
+
+impl Symbol<'_> {
+    pub fn into_owned(self) -> Symbol<'static> {
+        Symbol {
+            name: self.name.into_owned(),
+            remaining: self.remaining,
+            decision: self.decision,
+        }
+    }
+}
+
+impl PreprocessDecision {
+    pub fn is_ambiguous(&self) -> bool {
+        self.candidates.len() > 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Strict, // trailing input is an error
+    Lenient, // trailing input is returned in `Symbol::remaining`
+}
+
+impl<'a> Name<'a> {
+    fn identifier_from_str(ident: &'a str) -> Name<'a> {
+        Name::Identifier(Cow::Borrowed(ident))
+    }
+
+    // Template argument lists visible to a function, innermost first: the
+    // function's own `__tm__` list, then those of its enclosing classes.
+    fn template_scopes(&self) -> Vec<&[Name<'a>]> {
+        match self {
+            Name::WithArguments(base, _, _) | Name::WithReturnValue(base, _) => {
+                base.template_scopes()
+            }
+            Name::Template(base, args) => {
+                let mut scopes = vec![args.as_slice()];
+                scopes.extend(base.template_scopes());
+                scopes
+            }
+            Name::InName(leaf, parent) => {
+                let mut scopes = leaf.template_scopes();
+                scopes.extend(parent.template_scopes());
+                scopes
+            }
+            Name::Namespace(path) => path
+                .iter()
+                .rev()
+                .flat_map(|x| x.template_scopes())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // The function name the template scopes are read from, shared so the
+    // rest of the name can change meanwhile.
+    fn scoped_name(&self) -> Arc<Name<'a>> {
+        match self {
+            Name::WithArguments(base, _, _) => base.clone(),
+            Name::WithReturnValue(base, _) => base.scoped_name(),
+            name => Arc::new(name.clone()),
+        }
+    }
+
+    // `Z<index>_<level>Z` refers to argument `index` of the `level`-th scope;
+    // a value argument is no type, so it stays unresolved. The parameter
+    // itself is kept, so the name still mangles to the same symbol. Returns
+    // whether there was any parameter.
+    fn resolve_template_params(&mut self, scopes: &[&[Name<'a>]]) -> bool {
+        if let Name::TemplateParam {
+            index,
+            level,
+            resolved,
+        } = self
+        {
+            *resolved = scopes
+                .get(level.wrapping_sub(1))
+                .and_then(|args| args.get(index.wrapping_sub(1)))
+                .filter(|x| !matches!(x, Name::ValueArgument(_)))
+                .map(|x| Arc::new(x.clone()));
+            return true;
+        }
+
+        self.for_each_child_mut(&mut |child| child.resolve_template_params(scopes))
+    }
+
+    fn has_template_params(&self) -> bool {
+        let mut found = matches!(self, Name::TemplateParam { .. });
+        self.for_each_child(&mut |child| found |= child.has_template_params());
+        found
+    }
+
+    // `f` returns whether it changed a child, and so does this for any. A
+    // shared child is changed on a copy, kept only if `f` changed it, so
+    // subtrees without changes stay shared.
+    fn for_each_child_mut(&mut self, f: &mut impl FnMut(&mut Name<'a>) -> bool) -> bool {
+        fn shared<'a>(x: &mut Arc<Name<'a>>, f: &mut impl FnMut(&mut Name<'a>) -> bool) -> bool {
+            if let Some(x) = Arc::get_mut(x) {
+                return f(x);
+            }
+            let mut copy = Name::clone(x);
+            let changed = f(&mut copy);
+            if changed {
+                *x = Arc::new(copy);
+            }
+            changed
+        }
+        fn all<'a>(xs: &mut [Name<'a>], f: &mut impl FnMut(&mut Name<'a>) -> bool) -> bool {
+            xs.iter_mut().fold(false, |changed, x| f(x) | changed)
+        }
+
         match self {
-            Self::Identifier(x) => write!(f, "{x}"),
-            Self::BaseType(x) => write!(f, "{}", get_base_types()[x]),
-            Self::WithArguments(base, args) => write!(
-                f,
-                "{}({})",
-                base,
-                args.iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ),
-            Self::Template(base, args) => write!(
-                f,
-                "{}<{}>",
-                base,
-                args.iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ),
-            Self::Modifier(x, y) => match x {
-                Modifier::OnPrefix(s) => write!(f, "{} {}", s, y),
-                Modifier::OnSuffix(s) => write!(f, "{} {}", y, s),
+            Name::Identifier(_)
+            | Name::SpecialName(_)
+            | Name::BaseType(_)
+            | Name::TemplateParam { .. }
+            | Name::NameRef(_)
+            | Name::NameRepeat(_, _) => false,
+            Name::Conversion(x) | Name::Modifier(_, x) | Name::SizedArray(_, x) => shared(x, f),
+            Name::Thunk { target, .. } => shared(target, f),
+            Name::ValueArgument(value) => match value {
+                TemplateValue::Integer(x, _)
+                | TemplateValue::Char(x, _)
+                | TemplateValue::Enum(x, _)
+                | TemplateValue::Other(x, _) => shared(x, f),
+                TemplateValue::Bool(_) | TemplateValue::Address(_) => false,
             },
-            Self::Namespace(x) => write!(
-                f,
-                "{}",
-                x.iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join("::")
-            ),
-            Self::InName(leaf, parent) => write!(f, "{}::{}", parent, leaf),
-            Self::WithReturnValue(base, ret) => write!(f, "{} {}", ret, base),
-            Self::FunctionPointer(args, ret) => {
-                write!(
-                    f,
-                    "({} *({}))",
-                    ret,
-                    args.iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+            Name::WithArguments(x, xs, _)
+            | Name::Template(x, xs)
+            | Name::FunctionPointer(xs, x, _) => shared(x, f) | all(xs, f),
+            Name::Namespace(xs) => all(xs, f),
+            Name::InName(x, y) | Name::WithReturnValue(x, y) | Name::PointerToMember(x, y) => {
+                shared(x, f) | shared(y, f)
             }
-            Self::ValueArgument(ty, val) => write!(f, "{val} as {ty}"),
-            Self::SizedArray(size, ty) => write!(f, "{ty}[{size}]"),
-            Self::Names_Ref(x) => write!(f, "<NameRef {}>", x),
-            Self::Names_Multi(x, y) => write!(f, "<NameRepeat {} times of {}>", y, x),
         }
     }
-}
 
-impl Name {
-    fn identifier_from_string(ident: String) -> Name {
-        Name::Identifier(ident)
+    fn for_each_child<'b>(&'b self, f: &mut impl FnMut(&'b Name<'a>)) {
+        match self {
+            Name::Identifier(_)
+            | Name::SpecialName(_)
+            | Name::BaseType(_)
+            | Name::TemplateParam { .. }
+            | Name::NameRef(_)
+            | Name::NameRepeat(_, _) => {}
+            Name::Conversion(x) | Name::Modifier(_, x) | Name::SizedArray(_, x) => f(x),
+            Name::Thunk { target, .. } => f(target),
+            Name::ValueArgument(value) => match value {
+                TemplateValue::Integer(x, _)
+                | TemplateValue::Char(x, _)
+                | TemplateValue::Enum(x, _)
+                | TemplateValue::Other(x, _) => f(x),
+                TemplateValue::Bool(_) | TemplateValue::Address(_) => {}
+            },
+            Name::WithArguments(x, xs, _)
+            | Name::Template(x, xs)
+            | Name::FunctionPointer(xs, x, _) => {
+                f(x);
+                xs.iter().for_each(f);
+            }
+            Name::Namespace(xs) => xs.iter().for_each(f),
+            Name::InName(x, y) | Name::WithReturnValue(x, y) | Name::PointerToMember(x, y) => {
+                f(x);
+                f(y);
+            }
+        }
     }
-    fn Identifier_from_str(ident: &str) -> Name {
-        Name::Identifier(ident.to_string())
+
+    // Detaches the name from the symbol it was read from.
+    pub fn into_owned(self) -> Name<'static> {
+        let owned = |x: Arc<Name<'a>>| Arc::new(Arc::unwrap_or_clone(x).into_owned());
+        let all = |xs: Vec<Name<'a>>| -> Vec<Name<'static>> {
+            xs.into_iter().map(Name::into_owned).collect()
+        };
+        let string = |x: Cow<'a, str>| -> Cow<'static, str> { Cow::Owned(x.into_owned()) };
+
+        match self {
+            Name::Identifier(x) => Name::Identifier(string(x)),
+            Name::SpecialName(x) => Name::SpecialName(string(x)),
+            Name::Conversion(x) => Name::Conversion(owned(x)),
+            Name::BaseType(x) => Name::BaseType(x),
+            Name::WithArguments(x, xs, qualifiers) => {
+                Name::WithArguments(owned(x), all(xs), qualifiers)
+            }
+            Name::Template(x, xs) => Name::Template(owned(x), all(xs)),
+            Name::Modifier(modifier, x) => Name::Modifier(modifier, owned(x)),
+            Name::Namespace(xs) => Name::Namespace(all(xs)),
+            Name::InName(x, y) => Name::InName(owned(x), owned(y)),
+            Name::WithReturnValue(x, y) => Name::WithReturnValue(owned(x), owned(y)),
+            Name::FunctionPointer(xs, x, qualifiers) => {
+                Name::FunctionPointer(all(xs), owned(x), qualifiers)
+            }
+            Name::PointerToMember(x, y) => Name::PointerToMember(owned(x), owned(y)),
+            Name::ValueArgument(value) => Name::ValueArgument(match value {
+                TemplateValue::Integer(x, n) => TemplateValue::Integer(owned(x), n),
+                TemplateValue::Bool(x) => TemplateValue::Bool(x),
+                TemplateValue::Char(x, c) => TemplateValue::Char(owned(x), c),
+                TemplateValue::Enum(x, n) => TemplateValue::Enum(owned(x), n),
+                TemplateValue::Address(x) => TemplateValue::Address(string(x)),
+                TemplateValue::Other(x, raw) => TemplateValue::Other(owned(x), string(raw)),
+            }),
+            Name::SizedArray(size, x) => Name::SizedArray(size, owned(x)),
+            Name::TemplateParam {
+                index,
+                level,
+                resolved,
+            } => Name::TemplateParam {
+                index,
+                level,
+                resolved: resolved.map(owned),
+            },
+            Name::Thunk { adjustment, target } => Name::Thunk {
+                adjustment,
+                target: owned(target),
+            },
+            Name::NameRef(x) => Name::NameRef(x),
+            Name::NameRepeat(x, y) => Name::NameRepeat(x, y),
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        match self {
+            Name::WithArguments(_, _, qualifiers) => qualifiers.is_static,
+            Name::WithReturnValue(base, _) => base.is_static(),
+            Name::Thunk { target, .. } => target.is_static(),
+            _ => false,
+        }
+    }
+
+    // Number of operands of an overloaded operator function, counting the
+    // implicit `this` of members: `__mi__7Vector3Fv` is unary minus,
+    // `__mi__F7Vector3T1` is binary minus.
+    pub fn operator_arity(&self) -> Option<usize> {
+        let (name, args) = match self {
+            Name::WithArguments(name, args, _) => (name, args),
+            Name::WithReturnValue(base, _) => return base.operator_arity(),
+            Name::Thunk { target, .. } => return target.operator_arity(),
+            _ => return None,
+        };
+
+        let (code, is_member) = match &**name {
+            Name::SpecialName(code) => (code, false),
+            Name::InName(leaf, _) => match &**leaf {
+                Name::SpecialName(code) => (code, true),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        if !special_name(code)?.starts_with("operator") {
+            return None;
+        }
+
+        let explicit = match args.as_slice() {
+            [Name::BaseType('v')] => 0,
+            _ => args.len(),
+        };
+        Some(explicit + (is_member && !self.is_static()) as usize)
     }
 }
 
-fn read_name_identifier(input: &str) -> nom::IResult<&str, Name> {
-    let (input, length) = digit1(input)?;
-    let length = length.parse::<usize>().unwrap();
+fn number(input: &str) -> nom::IResult<&str, usize> {
+    map_res(digit1, str::parse::<usize>)(input)
+}
+
+// a zero length is no name, e.g. the `0` of a malformed `T10`
+fn read_name_identifier(input: &str) -> nom::IResult<&str, Name<'_>> {
+    let (input, length) = verify(number, |&x| x > 0)(input)?;
     let (input, ident) = take(length)(input)?;
 
-    Ok((input, Name::Identifier(ident.to_string())))
+    Ok((input, identifier(ident)))
+}
+
+fn identifier(ident: &str) -> Name<'_> {
+    if special_name(ident).is_some() {
+        return Name::SpecialName(Cow::Borrowed(ident));
+    }
+
+    if let Some(Ok(("", ty))) = ident.strip_prefix("__op").map(read_name) {
+        return Name::Conversion(Arc::new(ty));
+    }
+
+    // class templates keep their arguments inside the length-prefixed name
+    if let Some(i) = ident.find("__tm__").filter(|&i| i > 0) {
+        let mut name = Name::identifier_from_str(&ident[..i]);
+        let mut rest = &ident[i..];
+        while let Ok((new_rest, args)) = template(rest) {
+            name = Name::Template(Arc::new(name), args);
+            rest = new_rest;
+        }
+        if rest.is_empty() {
+            return name;
+        }
+    }
+
+    Name::identifier_from_str(ident)
 }
 
 fn extract_string(input: &str) -> nom::IResult<&str, &str> {
-    let (input, length) = digit1(input)?;
-    let length = length.parse::<usize>().unwrap();
+    let (input, length) = number(input)?;
     let (input, string) = take(length)(input)?;
 
     Ok((input, string))
 }
 fn extract_string_with_under_bar(input: &str) -> nom::IResult<&str, &str> {
-    let (input, length) = digit1(input)?;
-    let length = length.parse::<usize>().unwrap();
+    let (input, length) = number(input)?;
 
     let (input, _) = tag("_")(input)?;
     let (input, string) = take(length)(input)?;
@@ -126,140 +426,216 @@ fn extract_string_with_under_bar(input: &str) -> nom::IResult<&str, &str> {
     Ok((input, string))
 }
 
-fn read_modifier(input: &str) -> nom::IResult<&str, Name> {
-    let modifiers = get_name_modifiers()
-        .keys()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join("");
-
-    let (input, modifier) = one_of(modifiers.as_str())(input)?;
-    let modifier = get_name_modifiers().get(&modifier).unwrap().clone();
+fn read_modifier(input: &str) -> nom::IResult<&str, Name<'_>> {
+    let (input, modifier) = map_opt(anychar, name_modifier)(input)?;
 
     let (input, name) = read_name(input)?;
 
-    Ok((input, Name::Modifier(modifier, Box::new(name))))
+    Ok((input, Name::Modifier(modifier, Arc::new(name))))
 }
 
-fn value_argument(input: &str) -> nom::IResult<&str, Name> {
+fn value_argument(input: &str) -> nom::IResult<&str, Name<'_>> {
     let (input, _) = tag("X")(input)?;
     alt((
-        extract_string.map(|x| {
-            Name::ValueArgument(
-                Box::new(Name::Identifier_from_str("typename")),
-                x.to_string(),
-            )
-        }),
         permutation((read_name, tag("L_"), extract_string_with_under_bar))
-            .map(|(t, _, v)| Name::ValueArgument(Box::new(t), v.to_string())),
+            .map(|(t, _, v)| Name::ValueArgument(literal(t, v))),
+        extract_string.map(|x| Name::ValueArgument(TemplateValue::Address(Cow::Borrowed(x)))),
     ))
     .parse(input)
 }
-fn sized_array(input: &str) -> nom::IResult<&str, Name> {
+
+// literals are plain decimals, negative ones prefixed with `n`
+fn literal<'a>(ty: Name<'a>, value: &'a str) -> TemplateValue<'a> {
+    let (sign, digits) = match value.strip_prefix('n') {
+        Some(digits) => (-1, digits),
+        None => (1, value),
+    };
+    // any other sign is kept verbatim, since `mangle` writes it back as it came
+    let number = match digits.bytes().all(|x| x.is_ascii_digit()) {
+        true => digits.parse::<i128>().ok().map(|x| sign * x),
+        false => None,
+    };
+    let number = match number {
+        Some(number) => number,
+        None => return TemplateValue::Other(Arc::new(ty), Cow::Borrowed(value)),
+    };
+
+    let base = match &ty {
+        Name::Modifier(modifier, base) if modifier.is_prefix() => &**base,
+        ty => ty,
+    };
+    match base {
+        Name::BaseType('b') => TemplateValue::Bool(number != 0),
+        Name::BaseType('c' | 'w') => match u32::try_from(number) {
+            Ok(code) => TemplateValue::Char(Arc::new(ty), code),
+            Err(_) => TemplateValue::Integer(Arc::new(ty), number),
+        },
+        Name::BaseType('i' | 's' | 'l' | 'L') => TemplateValue::Integer(Arc::new(ty), number),
+        Name::Identifier(_) | Name::Namespace(_) | Name::InName(_, _) | Name::Template(_, _) => {
+            TemplateValue::Enum(Arc::new(ty), number)
+        }
+        _ => TemplateValue::Other(Arc::new(ty), Cow::Borrowed(value)),
+    }
+}
+fn sized_array(input: &str) -> nom::IResult<&str, Name<'_>> {
     let (input, _) = tag("A")(input)?;
-    let (input, size) = digit1(input)?;
-    let size = size.parse::<usize>().unwrap();
+    let (input, size) = number(input)?;
     let (input, _) = tag("_")(input)?;
     let (input, t) = read_name(input)?;
 
-    Ok((input, Name::SizedArray(size, Box::new(t))))
+    Ok((input, Name::SizedArray(size, Arc::new(t))))
 }
-fn type_ref(input: &str) -> nom::IResult<&str, Name> {
-    // TODO: Z\d_\dZ
-    let (input, t) = delimited(
+fn type_ref(input: &str) -> nom::IResult<&str, Name<'_>> {
+    let (input, (index, level)) = delimited(
         tag("Z"),
-        terminated(digit1, opt(permutation((tag("_"), digit1)))),
+        tuple((number, opt(preceded(tag("_"), number)))),
         tag("Z"),
     )(input)?;
-    let t = t.parse::<usize>().unwrap();
+    let level = level.unwrap_or(1);
+
+    Ok((
+        input,
+        Name::TemplateParam {
+            index,
+            level,
+            resolved: None,
+        },
+    ))
+}
+
+// back-reference numbers are a single digit, or longer ones closed by `_`
+fn long_ref_number(input: &str) -> nom::IResult<&str, usize> {
+    terminated(
+        map_res(verify(digit1, |x: &str| x.len() > 1), str::parse::<usize>),
+        tag("_"),
+    )(input)
+}
 
-    Ok((input, Name::Identifier(t.to_string())))
+fn short_ref_number(input: &str) -> nom::IResult<&str, usize> {
+    map(one_of("0123456789"), |x| x.to_digit(10).unwrap() as usize)(input)
 }
 
-fn read_name_ref(input: &str) -> nom::IResult<&str, Name> {
+fn ref_number(input: &str) -> nom::IResult<&str, usize> {
+    alt((long_ref_number, short_ref_number))(input)
+}
+
+fn read_name_ref(input: &str) -> nom::IResult<&str, Name<'_>> {
     let (input, _) = tag("T")(input)?;
-    let (input, index) = one_of("0123456789")(input)?;
+    let (input, index) = ref_number(input)?;
 
-    Ok((
-        input,
-        Name::Names_Ref(index.to_string().parse::<usize>().unwrap()),
-    ))
+    Ok((input, Name::NameRef(index)))
 }
 
-fn read_name_repeat(input: &str) -> nom::IResult<&str, Name> {
+fn read_name_repeat(input: &str) -> nom::IResult<&str, Name<'_>> {
     let (input, _) = tag("N")(input)?;
-    let (input, index) = one_of("0123456789")(input)?;
-    let (input, index2) = one_of("0123456789")(input)?;
-    let index = index.to_string().parse::<usize>().unwrap();
-    let index2 = index2.to_string().parse::<usize>().unwrap();
-    Ok((input, Name::Names_Multi(index, index2)))
+    let (input, (count, index)) = alt((
+        tuple((long_ref_number, ref_number)),
+        tuple((short_ref_number, ref_number)),
+    ))(input)?;
+    Ok((input, Name::NameRepeat(count, index)))
 }
 
-fn read_names(input: &str) -> nom::IResult<&str, Vec<Name>> {
-    let (input, names) = many0(alt((
-        read_name,
-        read_name_ref,
-        read_name_repeat,
-        value_argument,
-    )))(input)?;
+const MAX_REPEAT: usize = 256;
 
+fn read_names(mut input: &str) -> nom::IResult<&str, Vec<Name<'_>>> {
     let mut ret: Vec<Name> = Vec::new();
 
-    for name in names {
+    loop {
+        let res = alt((read_name, read_name_ref, read_name_repeat, value_argument))(input);
+        let (new_input, name) = match res {
+            Ok(x) => x,
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e),
+        };
+
+        // references are 1-based; the list ends at one outside of `ret`,
+        // which is left unparsed
         match name {
-            Name::Names_Ref(index) => ret.push(ret[index - 1].clone()),
-            Name::Names_Multi(count, index) => {
-                for _ in 1..=count {
-                    ret.push(ret[index - 1].clone());
+            Name::NameRef(index) => match index.checked_sub(1).and_then(|i| ret.get(i)) {
+                Some(name) => ret.push(name.clone()),
+                None => break,
+            },
+            Name::NameRepeat(count, index) => match index.checked_sub(1).and_then(|i| ret.get(i)) {
+                Some(name) if count <= MAX_REPEAT => {
+                    ret.extend(std::iter::repeat_n(name.clone(), count))
                 }
-            }
+                _ => break,
+            },
             _ => ret.push(name),
         }
+        input = new_input;
     }
 
     Ok((input, ret))
 }
 
-fn arguments(input: &str) -> nom::IResult<&str, Vec<Name>> {
-    preceded(alt((tag("F"), tag("CF"), tag("SF"))), read_names)(input)
+fn arguments(input: &str) -> nom::IResult<&str, (FunctionQualifiers, Vec<Name<'_>>)> {
+    let (input, (is_static, is_const, is_volatile, _)) =
+        tuple((opt(tag("S")), opt(tag("C")), opt(tag("V")), tag("F")))(input)?;
+    let (input, args) = read_names(input)?;
+    let qualifiers = FunctionQualifiers {
+        is_static: is_static.is_some(),
+        static_marker: false,
+        is_const: is_const.is_some(),
+        is_volatile: is_volatile.is_some(),
+    };
+
+    Ok((input, (qualifiers, args)))
 }
 
-fn namespace(input: &str) -> nom::IResult<&str, Name> {
+fn namespace(input: &str) -> nom::IResult<&str, Name<'_>> {
     let (input, _) = tag("Q")(input)?;
-    let (input, depth) = digit1(input)?;
-    let depth = depth.parse::<usize>().unwrap();
-    let (input, _) = tag("_")(input)?;
+    // Q<n>_, or Q_<n>_ once the depth needs more than one digit
+    let (input, depth) = terminated(preceded(opt(tag("_")), number), tag("_"))(input)?;
     let (input, path) = count(read_name, depth)(input)?;
 
     Ok((input, Name::Namespace(path)))
 }
 
-fn base_type(input: &str) -> nom::IResult<&str, Name> {
-    let base_types = get_base_types()
-        .keys()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join("");
-
-    let (input, base_type) = one_of(base_types.as_str())(input)?;
+fn base_type(input: &str) -> nom::IResult<&str, Name<'_>> {
+    let (input, base_type) = verify(anychar, |&x| base_type_name(x).is_some())(input)?;
 
     Ok((input, Name::BaseType(base_type)))
 }
 
-fn function_pointer(input: &str) -> nom::IResult<&str, Name> {
+fn function_pointer(input: &str) -> nom::IResult<&str, Name<'_>> {
     let (input, _) = tag("F")(input)?;
     permutation((read_names, tag("_"), read_name))
         .map(|x| {
             let (args, _, ret) = x;
-            Name::FunctionPointer(args, Box::new(ret))
+            Name::FunctionPointer(args, Arc::new(ret), FunctionQualifiers::default())
         })
         .parse(input)
 }
 
-fn read_name(input: &str) -> nom::IResult<&str, Name> {
-    let (mut input, mut name) = alt((
+fn pointer_to_member(input: &str) -> nom::IResult<&str, Name<'_>> {
+    let (input, _) = tag("M")(input)?;
+    let (input, class) = read_name(input)?;
+    // only member functions can be cv-qualified, e.g. M3FooCFi_v
+    let (input, member) = alt((
+        tuple((opt(tag("C")), opt(tag("V")), function_pointer)).map(
+            |(is_const, is_volatile, mut function)| {
+                if let Name::FunctionPointer(_, _, qualifiers) = &mut function {
+                    qualifiers.is_const = is_const.is_some();
+                    qualifiers.is_volatile = is_volatile.is_some();
+                }
+                function
+            },
+        ),
+        read_name,
+    ))(input)?;
+
+    Ok((
+        input,
+        Name::PointerToMember(Arc::new(class), Arc::new(member)),
+    ))
+}
+
+fn read_name(input: &str) -> nom::IResult<&str, Name<'_>> {
+    let (input, name) = alt((
         read_name_identifier,
         namespace,
+        pointer_to_member,
         read_modifier,
         type_ref,
         base_type,
@@ -267,18 +643,26 @@ fn read_name(input: &str) -> nom::IResult<&str, Name> {
         sized_array,
     ))(input)?;
 
+    name_suffixes(input, name)
+}
+
+// the template arguments and parents following a name
+fn name_suffixes<'a>(mut input: &'a str, mut name: Name<'a>) -> nom::IResult<&'a str, Name<'a>> {
     loop {
-        let res = template(input);
-        if let Ok((new_input, args)) = res {
+        if let (new_input, Some(args)) = opt(template)(input)? {
             input = new_input;
-            name = Name::Template(Box::new(name), args);
+            name = Name::Template(Arc::new(name), args);
             continue;
         }
 
-        let res = preceded(tag("__"), read_name)(input);
-        if let Ok((new_input, parent)) = res {
+        // `__F` starts the parameter list of a function, not a parent, and
+        // builtin types have no scope
+        if input.starts_with("__F") || matches!(name, Name::BaseType(_)) {
+            break;
+        }
+        if let (new_input, Some(parent)) = opt(parent)(input)? {
             input = new_input;
-            name = Name::InName(Box::new(name), Box::new(parent));
+            name = Name::InName(Arc::new(name), Arc::new(parent));
             continue;
         }
         break;
@@ -287,34 +671,57 @@ fn read_name(input: &str) -> nom::IResult<&str, Name> {
     Ok((input, name))
 }
 
-fn read_function(input: &str) -> nom::IResult<&str, Name> {
-    let (mut input, mut name) = read_name(input)?;
+// `__<class>`; `__S` and `__F` are markers of the function, not scopes
+fn parent(input: &str) -> nom::IResult<&str, Name<'_>> {
+    verify(preceded(tag("__"), read_name), is_scope)(input)
+}
+
+fn is_scope(name: &Name) -> bool {
+    match name {
+        Name::Identifier(_) | Name::Namespace(_) | Name::Template(_, _) => true,
+        Name::InName(leaf, parent) => is_scope(leaf) && is_scope(parent),
+        _ => false,
+    }
+}
+
+// the rest of a function after its leading name
+fn read_function<'a>(mut input: &'a str, mut name: Name<'a>) -> nom::IResult<&'a str, Name<'a>> {
     loop {
-        let res: nom::IResult<&str, &str> = tag("__S")(input);
-        if let Ok((new_input, _)) = res {
+        // `__S` marks a static member right ahead of its parameters; a
+        // dangling one is left unparsed, as is one after a free function
+        let is_member = matches!(name, Name::InName(_, _));
+        let res = opt(preceded(tag("__S"), verify(arguments, |_| is_member)))(input)?;
+        if let (new_input, Some((mut qualifiers, args))) = res {
             input = new_input;
-            // TODO: make name to static function
+            qualifiers.is_static = true;
+            qualifiers.static_marker = true;
+            name = Name::WithArguments(Arc::new(name), args, qualifiers);
             continue;
         }
 
-        let res = preceded(opt(tag("__")), arguments)(input);
-        if let Ok((new_input, args)) = res {
+        let res = opt(preceded(
+            opt(tag("__")),
+            verify(arguments, |(qualifiers, _)| {
+                is_member || !qualifiers.is_static
+            }),
+        ))(input)?;
+        if let (new_input, Some((qualifiers, args))) = res {
             input = new_input;
-            name = Name::WithArguments(Box::new(name), args);
+            name = Name::WithArguments(Arc::new(name), args, qualifiers);
             continue;
         }
 
-        let res = preceded(tag("_"), read_name)(input);
-        if let Ok((new_input, return_value_type)) = res {
+        let res = opt(preceded(tag("_"), read_name))(input)?;
+        if let (new_input, Some(return_value_type)) = res {
             input = new_input;
-            name = Name::WithReturnValue(Box::new(name), Box::new(return_value_type));
+            name = Name::WithReturnValue(Arc::new(name), Arc::new(return_value_type));
             continue;
         }
 
-        let res = preceded(tag("__"), read_name)(input);
-        if let Ok((new_input, parent)) = res {
+        let res = opt(parent)(input)?;
+        if let (new_input, Some(parent)) = res {
             input = new_input;
-            name = Name::InName(Box::new(name), Box::new(parent));
+            name = Name::InName(Arc::new(name), Arc::new(parent));
             continue;
         }
         break;
@@ -323,15 +730,16 @@ fn read_function(input: &str) -> nom::IResult<&str, Name> {
     Ok((input, name))
 }
 
-fn template(input: &str) -> nom::IResult<&str, Vec<Name>> {
+fn template(input: &str) -> nom::IResult<&str, Vec<Name<'_>>> {
     let (input, string) = preceded(tag("__tm__"), extract_string)(input)?;
-    let string = &string[1..];
+    let (string, _) = tag("_")(string)?;
     //template_value
     let (string, names) = read_names(string)?;
 
+    // the length says where the list ends, so anything left is malformed
     if !string.is_empty() {
-        return Err(nom::Err::Error(nom::error::Error::new(
-            input,
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            string,
             nom::error::ErrorKind::NonEmpty,
         )));
     }
@@ -339,139 +747,325 @@ fn template(input: &str) -> nom::IResult<&str, Vec<Name>> {
     Ok((input, names))
 }
 
-fn decompress(input: &str) -> nom::IResult<&str, String> {
-    let input = if input.starts_with("__ghs_thunk__") {
-        &input[25..]
-    } else {
-        input
-    };
+const THUNK_PREFIX: &str = "__ghs_thunk__";
 
-    let input = if input.starts_with("__CPR") {
-        let input = &input[5..];
-
-        let (input, decompressed_length) = digit1(input)?;
-        let (input, _) = tag("__")(input)?;
-        let raw_data = input;
-        let _decompressed_length = decompressed_length.parse::<usize>().unwrap();
-
-        let tokens = raw_data.split("J");
-        let mut decompressed = "".to_string();
-
-        for (i, c) in tokens.enumerate() {
-            if i % 2 == 0 {
-                decompressed += c;
-            } else {
-                if c.is_empty() {
-                    decompressed += "J";
-                } else {
-                    let offset = c.parse::<usize>().unwrap();
-
-                    let mut s = "".to_string();
-                    for c in decompressed[offset..].chars() {
-                        s.push(c);
-                    }
-
-                    let t = extract_string(&s).unwrap().1;
-                    decompressed += &(t.len().to_string() + t);
-                }
-            }
-        }
-        // println!("{}", decompressed);
-        decompressed
-    } else {
-        input.to_string()
+// __ghs_thunk__0x<hex>__<symbol>, where <hex> is the this-adjustment as a
+// two's complement number (32-bit when it has at most 8 digits)
+fn parse_thunk(input: &str) -> Result<(Option<i64>, &str), DemangleError> {
+    let rest = match input.strip_prefix(THUNK_PREFIX) {
+        Some(rest) => rest,
+        None => return Ok((None, input)),
     };
+    let error = |remaining: &str| DemangleError::new(Stage::Thunk, input, remaining);
 
-    Ok(("", input))
-}
-
-fn _demangle(input: &str) -> nom::IResult<&str, Name> {
-    let (input, name_obj) = read_function(input)?;
-    // if !input.is_empty() {
-    //     println!("");
-    //     println!("### Error ###");
-    //     println!("name: {:}", name_obj);
-    //     println!("Remain: {:}", input);
-    //     return Err(nom::Err::Error(nom::error::Error::new(
-    //         input,
-    //         nom::error::ErrorKind::NonEmpty,
-    //     )));
-    // }
-    Ok((input, name_obj))
+    let digits = rest.strip_prefix("0x").ok_or_else(|| error(rest))?;
+    let end = digits
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(digits.len());
+    let (hex, target) = digits.split_at(end);
+    let adjustment = match hex.len() {
+        1..=8 => u32::from_str_radix(hex, 16).map(|x| x as i32 as i64),
+        9..=16 => u64::from_str_radix(hex, 16).map(|x| x as i64),
+        _ => return Err(error(digits)),
+    }
+    .map_err(|_| error(digits))?;
+
+    match target.strip_prefix("__") {
+        Some(target) if !target.is_empty() => Ok((Some(adjustment), target)),
+        _ => Err(error(target)),
+    }
 }
 
-fn preprocess(x: String, dunder_search_index: usize) -> String {
-    if x.starts_with("__")
-        && x[2..]
-            .to_string()
-            .into_bytes()
-            .iter()
-            .all(|x| x.is_ascii_alphanumeric())
-    {
-        println!("{x} -> Returns with rule #0");
-        return format!("{}{}", x.len(), x);
+fn _demangle<'a>(leading: Name<'a>, input: &'a str, mode: Mode) -> nom::IResult<&'a str, Name<'a>> {
+    let (input, name_obj) = name_suffixes(input, leading)?;
+    let (input, mut name_obj) = read_function(input, name_obj)?;
+    if name_obj.has_template_params() {
+        let scoped = name_obj.scoped_name();
+        name_obj.resolve_template_params(&scoped.template_scopes());
+    }
+    if mode == Mode::Strict && !input.is_empty() {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Eof,
+        )));
     }
+    Ok((input, name_obj))
+}
 
-    if x.starts_with("__") && !x[2..].contains("__") {
-        // __[^__]*
-        println!("{x} -> Returns with rule #1");
-        return format!("{}{}", x.len(), x);
+// Parses `x` with its leading identifier `split` bytes long. Errors inside
+// template arguments point into the slice holding the argument list, which
+// is found back in `x` by its address.
+fn parse_split(x: &str, split: usize, mode: Mode) -> Result<(Name<'_>, usize), DemangleError> {
+    match _demangle(identifier(&x[..split]), &x[split..], mode) {
+        Ok((remaining, name)) => Ok((name, remaining.len())),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            let stage = match e.code {
+                nom::error::ErrorKind::Eof => Stage::TrailingInput,
+                _ => Stage::Grammar,
+            };
+            let offset = (e.input.as_ptr() as usize).wrapping_sub(x.as_ptr() as usize);
+            let remaining = x.get(offset..).unwrap_or(e.input);
+            Err(DemangleError::new(stage, x, remaining))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(DemangleError::new(Stage::Grammar, x, x)),
     }
+}
 
-    let mut predictions = vec![];
+// Split points worth trying for the leading identifier: every `__`
+// boundary, in order, overlapping ones included, so `foo___3FooFv` can
+// split after `foo_`. Template arguments belong to the identifier, so
+// `__tm__` is a boundary only after its argument list.
+fn boundaries(x: &str) -> Result<Vec<usize>, DemangleError> {
+    let error = |offset: usize| Err(DemangleError::new(Stage::Preprocess, x, &x[offset..]));
 
-    if let Some(i) = x.find("__F") {
-        predictions.push(i);
+    if let Some(offset) = x.find(|c: char| !c.is_ascii()) {
+        return error(offset);
     }
-
-    if let Some(i) = x.find("__tm__") {
-        predictions.push(i);
+    if x.is_empty() {
+        return error(0);
+    }
+    // static initialization and destruction functions are named after their
+    // file, e.g. `__sti___9_main_cpp`, which is no scope
+    if x.starts_with("__sti__") || x.starts_with("__std__") {
+        return Ok(vec![]);
     }
 
-    for i in 1..9 {
-        if let Some(i) = x.find(format!("Q{i}_").as_str()) {
-            predictions.push(i - 2);
+    let mut boundaries = vec![];
+    for i in (1..x.len()).filter(|&i| x[i..].starts_with("__")) {
+        match &x[i..] {
+            // only the leading identifier's own arguments
+            rest if rest.starts_with("__tm__") && !x[1..i].contains("__") => {
+                if let Ok((rest, _)) = template(rest) {
+                    boundaries.push(x.len() - rest.len());
+                }
+            }
+            rest if rest.starts_with("__tm__") || x[..i + 2].ends_with("__tm__") => {}
+            _ => boundaries.push(i),
         }
     }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    Ok(boundaries)
+}
+
+// What the grammar makes of `x` after each boundary. `complete` holds every
+// distinct full parse, most likely first: the one whose scopes `known`
+// supports best, then the one splitting first.
+struct Readings<'a> {
+    complete: Vec<(usize, Name<'a>)>,
+    longest: Option<(usize, Name<'a>, usize)>,
+    furthest: Option<DemangleError>,
+    progressed: bool, // anything was read past a boundary
+}
 
-    if predictions.is_empty() {
-        if x.split_once("__").map(|(a, b)| {
-            for c in b.chars() {
-                if !c.is_ascii_alphanumeric() {
-                    return false;
+fn readings<'a>(
+    x: &'a str,
+    mode: Mode,
+    known: &HashSet<String>,
+) -> Result<Readings<'a>, DemangleError> {
+    let mut readings = Readings {
+        complete: vec![],
+        longest: None,
+        furthest: None,
+        progressed: false,
+    };
+    for split in boundaries(x)? {
+        match parse_split(x, split, mode) {
+            Ok((name, 0)) => {
+                if !readings.complete.iter().any(|(_, x)| *x == name) {
+                    readings.complete.push((split, name));
+                }
+            }
+            Ok((name, remaining)) => {
+                readings.progressed |= x.len() - remaining > split;
+                if readings.longest.as_ref().is_none_or(|x| remaining < x.2) {
+                    readings.longest = Some((split, name, remaining));
+                }
+            }
+            Err(e) => {
+                readings.progressed |= e.offset > split;
+                if readings
+                    .furthest
+                    .as_ref()
+                    .is_none_or(|x| e.offset > x.offset)
+                {
+                    readings.furthest = Some(e);
                 }
             }
-            true
-        }) == Some(true)
-        {
-            println!("{x} -> Returns with rule #3");
-            return format!("{}{}", x.find("__").unwrap(), x);
         }
-        println!("{x} -> Returns with rule #2");
-        return format!("{}{}", x.len(), x);
     }
+    // stable, so equal evidence keeps the first split first
+    readings
+        .complete
+        .sort_by_cached_key(|(_, name)| Reverse(evidence(name, known)));
+    Ok(readings)
+}
 
-    let mut min_index = 0;
-    for i in 0..predictions.len() {
-        if predictions[i] < predictions[min_index] {
-            min_index = i;
-        }
+// The whole symbol as one plain identifier, when the grammar reads nothing
+// after any boundary.
+fn whole(x: &str, mode: Mode) -> Result<(PreprocessDecision, Name<'_>, usize), DemangleError> {
+    let decision = PreprocessDecision {
+        rule: PreprocessRule::Whole,
+        candidates: vec![x.len()],
+        chosen: x.len(),
+    };
+    let (name, remaining) = parse_split(x, x.len(), mode)?;
+    Ok((decision, name, remaining))
+}
+
+// Keeps the boundaries after which the grammar consumes the whole symbol,
+// preferring the first unless `known` says otherwise. When the grammar reads
+// nothing after any boundary, the symbol is a plain identifier.
+fn preprocess<'a>(
+    x: &'a str,
+    mode: Mode,
+    known: &HashSet<String>,
+) -> Result<(PreprocessDecision, Name<'a>, usize), DemangleError> {
+    let Readings {
+        mut complete,
+        longest,
+        furthest,
+        progressed,
+    } = readings(x, mode, known)?;
+
+    if !complete.is_empty() {
+        let mut candidates = complete.iter().map(|(split, _)| *split).collect::<Vec<_>>();
+        candidates.sort_unstable();
+        let (chosen, name) = complete.swap_remove(0);
+        let rule = match chosen == candidates[0] {
+            true => PreprocessRule::Boundary,
+            false => PreprocessRule::KnownNames,
+        };
+        let decision = PreprocessDecision {
+            rule,
+            candidates,
+            chosen,
+        };
+        return Ok((decision, name, 0));
+    }
+    if !progressed {
+        return whole(x, mode);
+    }
+    if let Some((split, name, remaining)) = longest {
+        let decision = PreprocessDecision {
+            rule: PreprocessRule::LongestPrefix,
+            candidates: vec![split],
+            chosen: split,
+        };
+        return Ok((decision, name, remaining));
     }
-    println!("{x} -> Selected #{min_index} of {predictions:?}");
-    return format!("{}{}", predictions[min_index], x);
+    Err(furthest.unwrap())
 }
 
-pub fn demangle(x: String) -> Name {
-    let x = if let Ok(x) = decompress(&x) {
-        x.1
-    } else {
-        return Name::Identifier(x);
+pub fn try_demangle(input: &str) -> Result<Symbol<'_>, DemangleError> {
+    try_demangle_with(input, Mode::Strict)
+}
+
+pub fn try_demangle_with(input: &str, mode: Mode) -> Result<Symbol<'_>, DemangleError> {
+    demangle_symbol(input, mode, &HashSet::new())
+}
+
+fn with_thunk(adjustment: Option<i64>, name: Name) -> Name {
+    match adjustment {
+        Some(adjustment) => Name::Thunk {
+            adjustment,
+            target: Arc::new(name),
+        },
+        None => name,
+    }
+}
+
+// Names borrow from `input`, unless `__CPR` expansion made a new string.
+fn demangle_symbol<'a>(
+    input: &'a str,
+    mode: Mode,
+    known: &HashSet<String>,
+) -> Result<Symbol<'a>, DemangleError> {
+    let (adjustment, x) = parse_thunk(input)?;
+    let (decision, name, remaining) = match cpr::decompress_in(input, x)? {
+        Cow::Borrowed(x) => {
+            let (decision, name, remaining) = preprocess(x, mode, known)?;
+            (decision, name, x[x.len() - remaining..].to_string())
+        }
+        Cow::Owned(x) => {
+            let (decision, name, remaining) = preprocess(&x, mode, known)?;
+            let remaining = x[x.len() - remaining..].to_string();
+            (decision, name.into_owned(), remaining)
+        }
     };
-    let x = preprocess(x, 0);
-    let x = if let Ok((_, x)) = _demangle(&x) {
-        x
-    } else {
-        return Name::Identifier(x);
+
+    Ok(Symbol {
+        name: with_thunk(adjustment, name),
+        remaining,
+        decision,
+    })
+}
+
+// Out of 100, shared by the readings of a symbol by their known-name
+// evidence: each weighs 1 plus its known scopes. Readings only the first-split
+// tie-break tells apart score the same; a single reading scores 100.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Score(pub u32);
+
+// Every full parse of `input`, ranked the way `try_demangle` picks one, so
+// the first is its reading. The whole symbol is a plain identifier only when
+// nothing else parses.
+pub fn demangle_candidates(input: &str) -> Vec<(Name<'_>, Score)> {
+    candidates_symbol(input, &HashSet::new())
+}
+
+pub(crate) fn candidates_symbol<'a>(
+    input: &'a str,
+    known: &HashSet<String>,
+) -> Vec<(Name<'a>, Score)> {
+    let (adjustment, x) = match parse_thunk(input) {
+        Ok(x) => x,
+        Err(_) => return vec![],
+    };
+    let candidates = match cpr::decompress_in(input, x) {
+        Ok(Cow::Borrowed(x)) => candidates(x, known),
+        Ok(Cow::Owned(x)) => candidates(&x, known)
+            .into_iter()
+            .map(|(name, score)| (name.into_owned(), score))
+            .collect(),
+        Err(_) => return vec![],
+    };
+
+    candidates
+        .into_iter()
+        .map(|(name, score)| (with_thunk(adjustment, name), score))
+        .collect()
+}
+
+fn candidates<'a>(x: &'a str, known: &HashSet<String>) -> Vec<(Name<'a>, Score)> {
+    let readings = match readings(x, Mode::Strict, known) {
+        Ok(readings) => readings,
+        Err(_) => return vec![],
     };
-    x
+    if readings.complete.is_empty() {
+        return match whole(x, Mode::Strict) {
+            Ok((_, name, 0)) if !readings.progressed => vec![(name, Score(100))],
+            _ => vec![],
+        };
+    }
+
+    let weights = readings
+        .complete
+        .iter()
+        .map(|(_, name)| 1.0 + evidence(name, known) as f64)
+        .collect::<Vec<_>>();
+    let total = weights.iter().sum::<f64>();
+    readings
+        .complete
+        .into_iter()
+        .zip(weights)
+        .map(|((_, name), weight)| (name, Score((100.0 * weight / total).round() as u32)))
+        .collect()
+}
+
+pub fn demangle(x: String) -> Name<'static> {
+    match try_demangle(&x) {
+        Ok(symbol) => symbol.name.into_owned(),
+        Err(_) => Name::Identifier(Cow::Owned(x)),
+    }
 }
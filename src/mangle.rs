@@ -0,0 +1,185 @@
+use crate::{FunctionQualifiers, Name, TemplateValue, MAX_REPEAT};
+
+impl Name<'_> {
+    // Inverse of `demangle`: the leading name of a symbol is written without
+    // its length, everything after it the way `read_name` expects. The
+    // result demangles back to the same name, but need not be the symbol
+    // the toolchain emits: where it has a choice, e.g. between `ii` and
+    // `iT1`, this picks its own.
+    pub fn mangle(&self) -> String {
+        match self {
+            Name::Thunk { adjustment, target } => {
+                let adjustment = match i32::try_from(*adjustment) {
+                    Ok(x) if x >= 0 => format!("{x:x}"),
+                    Ok(x) => format!("{:08x}", x as u32),
+                    Err(_) => format!("{:016x}", *adjustment as u64),
+                };
+                format!("__ghs_thunk__0x{adjustment}__{}", target.mangle())
+            }
+            Name::WithReturnValue(base, ret) => format!("{}_{}", base.mangle(), mangle_type(ret)),
+            Name::WithArguments(base, args, qualifiers) => {
+                // a file-static function has no marker
+                let qualifiers = &FunctionQualifiers {
+                    is_static: qualifiers.is_static && matches!(**base, Name::InName(_, _)),
+                    ..*qualifiers
+                };
+                // members end in their class name and templates in their
+                // arguments, other free functions need `__`; `__S` is one too
+                let separator = match **base {
+                    _ if qualifiers.is_static && qualifiers.static_marker => "__S",
+                    Name::InName(_, _) | Name::Template(_, _) => "",
+                    _ => "__",
+                };
+                format!(
+                    "{}{separator}{}F{}",
+                    mangle_leading(base),
+                    mangle_qualifiers(qualifiers),
+                    mangle_names(args)
+                )
+            }
+            name => mangle_leading(name),
+        }
+    }
+}
+
+fn mangle_leading(name: &Name) -> String {
+    match name {
+        Name::Identifier(x) | Name::SpecialName(x) => x.to_string(),
+        Name::Conversion(ty) => format!("__op{}", mangle_type(ty)),
+        Name::Template(base, args) => mangle_leading(base) + &mangle_template_args(args),
+        Name::InName(leaf, parent) => {
+            format!("{}__{}", mangle_leading(leaf), mangle_type(parent))
+        }
+        name => mangle_type(name),
+    }
+}
+
+fn mangle_qualifiers(qualifiers: &FunctionQualifiers) -> String {
+    [
+        (qualifiers.is_static && !qualifiers.static_marker, "S"),
+        (qualifiers.is_const, "C"),
+        (qualifiers.is_volatile, "V"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, x)| *x)
+    .collect()
+}
+
+fn length_prefixed(x: &str) -> String {
+    format!("{}{x}", x.len())
+}
+
+// single digits as is, longer numbers closed by `_`
+fn ref_number(x: usize) -> String {
+    match x {
+        0..=9 => x.to_string(),
+        _ => format!("{x}_"),
+    }
+}
+
+fn mangle_template_args(args: &[Name]) -> String {
+    let args = format!("_{}", mangle_names(args));
+    format!("__tm__{}{args}", args.len())
+}
+
+// Repeated parameters become `T<n>` (one copy of parameter n) or `N<c><n>`
+// (c copies, at most `MAX_REPEAT`), unless spelling them out is no longer.
+// The toolchain does not always agree, e.g. with `foo__FiT1`; either way
+// the parameters demangle the same.
+fn mangle_names(names: &[Name]) -> String {
+    let mut mangled = String::new();
+
+    let mut i = 0;
+    while i < names.len() {
+        let name = &names[i];
+        let encoded = mangle_type(name);
+        let first = names[..i].iter().position(|x| x == name);
+
+        let back_reference = first.filter(|_| encoded.len() > 1).map(|first| {
+            let run = names[i..]
+                .iter()
+                .take_while(|x| *x == name)
+                .take(MAX_REPEAT)
+                .count();
+            match run {
+                1 => (1, format!("T{}", ref_number(first + 1))),
+                run => (
+                    run,
+                    format!("N{}{}", ref_number(run), ref_number(first + 1)),
+                ),
+            }
+        });
+        match back_reference {
+            Some((run, reference)) if reference.len() < encoded.len() * run => {
+                mangled += &reference;
+                i += run;
+            }
+            _ => {
+                mangled += &encoded;
+                i += 1;
+            }
+        }
+    }
+
+    mangled
+}
+
+fn mangle_type(name: &Name) -> String {
+    match name {
+        Name::Identifier(x) | Name::SpecialName(x) => length_prefixed(x),
+        Name::Conversion(ty) => length_prefixed(&format!("__op{}", mangle_type(ty))),
+        Name::BaseType(x) => x.to_string(),
+        // class templates keep their arguments inside the length-prefixed name
+        Name::Template(base, args) => match &**base {
+            Name::Identifier(_) | Name::Template(_, _) => {
+                length_prefixed(&(mangle_leading(base) + &mangle_template_args(args)))
+            }
+            base => mangle_type(base) + &mangle_template_args(args),
+        },
+        Name::Modifier(modifier, ty) => format!("{}{}", modifier.code(), mangle_type(ty)),
+        Name::Namespace(path) => {
+            let depth = match path.len() {
+                0..=9 => format!("Q{}_", path.len()),
+                depth => format!("Q_{depth}_"),
+            };
+            depth + &path.iter().map(mangle_type).collect::<String>()
+        }
+        Name::InName(leaf, parent) => format!("{}__{}", mangle_type(leaf), mangle_type(parent)),
+        Name::FunctionPointer(args, ret, qualifiers) => format!(
+            "{}F{}_{}",
+            mangle_qualifiers(qualifiers),
+            mangle_names(args),
+            mangle_type(ret)
+        ),
+        Name::PointerToMember(class, member) => {
+            format!("M{}{}", mangle_type(class), mangle_type(member))
+        }
+        Name::ValueArgument(value) => {
+            let literal = |ty: &Name, x: String| format!("X{}L_{}_{x}", mangle_type(ty), x.len());
+            let number = |x: i128| match x {
+                x if x < 0 => format!("n{}", x.unsigned_abs()),
+                x => x.to_string(),
+            };
+            match value {
+                TemplateValue::Integer(ty, x) | TemplateValue::Enum(ty, x) => {
+                    literal(ty, number(*x))
+                }
+                TemplateValue::Bool(x) => literal(&Name::BaseType('b'), (*x as u8).to_string()),
+                TemplateValue::Char(ty, x) => literal(ty, x.to_string()),
+                TemplateValue::Address(x) => format!("X{}", length_prefixed(x)),
+                TemplateValue::Other(ty, x) => literal(ty, x.to_string()),
+            }
+        }
+        Name::SizedArray(size, ty) => format!("A{size}_{}", mangle_type(ty)),
+        Name::TemplateParam {
+            index, level: 1, ..
+        } => format!("Z{index}Z"),
+        Name::TemplateParam { index, level, .. } => format!("Z{index}_{level}Z"),
+        Name::NameRef(index) => format!("T{}", ref_number(*index)),
+        Name::NameRepeat(count, index) => format!("N{}{}", ref_number(*count), ref_number(*index)),
+        Name::WithArguments(_, _, _) | Name::WithReturnValue(_, _) | Name::Thunk { .. } => {
+            name.mangle()
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use ghs_demangle::{demangle_batch, demangle_stream, try_demangle, Demangler, Mode};
+
+const SYMBOLS: &[&str] = &[
+    "foo__3BarF8VeryLongT1",
+    "Open__Q3_2nn2fs4FileFPCQ3_2nn2fs4PathQ3_2nn2fs4Mode",
+    "__CPR28__foo__3BarF8VeryLongJ10J",
+    "get__23Vector__tm__10_XiL_2_16FZ1Z",
+    "foo__3BarFv_x",
+    "main",
+    "",
+];
+
+// enough symbols to be split across threads, given several cores
+fn corpus() -> Vec<String> {
+    (0..20_000)
+        .map(|i| match i % 3 {
+            0 => format!("f{i}__3BarFi"),
+            _ => SYMBOLS[i % SYMBOLS.len()].to_string(),
+        })
+        .collect()
+}
+
+#[test]
+fn batch_keeps_order() {
+    let corpus = corpus();
+    let results = demangle_batch(corpus.iter().map(String::as_str));
+
+    assert_eq!(results.len(), corpus.len());
+    for (symbol, result) in corpus.iter().zip(results) {
+        assert_eq!(result.map(|x| x.name), try_demangle(symbol).map(|x| x.name));
+    }
+}
+
+#[test]
+fn stream_keeps_order() {
+    let corpus = corpus();
+    let results = demangle_stream(corpus.clone()).collect::<Vec<_>>();
+
+    assert_eq!(results.len(), corpus.len());
+    for (symbol, result) in corpus.iter().zip(results) {
+        assert_eq!(result.map(|x| x.name), try_demangle(symbol).map(|x| x.name));
+    }
+    assert_eq!(demangle_stream(Vec::<String>::new()).count(), 0);
+}
+
+#[test]
+fn batch_shares_context() {
+    let mut demangler = Demangler::new(Mode::Strict);
+    demangler.add_known("Baz");
+
+    let corpus = vec!["x__9Bar__3BazFv"; 5_000];
+    for result in demangler.demangle_batch(corpus.iter().copied()) {
+        assert_eq!(result.unwrap().name.to_string(), "Baz::x__9Bar()");
+    }
+    for result in demangler.demangle_stream(&corpus) {
+        assert_eq!(result.unwrap().name.to_string(), "Baz::x__9Bar()");
+    }
+}
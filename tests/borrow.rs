@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use ghs_demangle::{compress_cpr, try_demangle, Name};
+
+fn arguments<'a, 'b>(name: &'b Name<'a>) -> &'b [Name<'a>] {
+    match name {
+        Name::WithArguments(_, args, _) => args,
+        name => panic!("not a function: {name:?}"),
+    }
+}
+
+#[test]
+fn identifiers_borrow_from_symbol() {
+    let symbol = "Open__Q3_2nn2fs4FileFPC8VeryLong";
+    let name = try_demangle(symbol).unwrap().name;
+
+    let mut identifiers = vec![];
+    let Name::WithArguments(base, _, _) = &name else {
+        panic!("not a function: {name:?}");
+    };
+    let Name::InName(leaf, parent) = &**base else {
+        panic!("not a member: {base:?}");
+    };
+    identifiers.push(&**leaf);
+    if let Name::Namespace(path) = &**parent {
+        identifiers.extend(path);
+    }
+
+    assert_eq!(identifiers.len(), 4);
+    for identifier in identifiers {
+        let Name::Identifier(Cow::Borrowed(x)) = identifier else {
+            panic!("not borrowed: {identifier:?}");
+        };
+        assert!(symbol.as_bytes().as_ptr_range().contains(&x.as_ptr()));
+    }
+}
+
+#[test]
+fn back_references_share_subtree() {
+    let name = try_demangle("foo__3BarFPC8VeryLongT1").unwrap().name;
+    match arguments(&name) {
+        [Name::Modifier(_, first), Name::Modifier(_, second)] => {
+            assert!(Arc::ptr_eq(first, second))
+        }
+        args => panic!("unexpected arguments: {args:?}"),
+    }
+}
+
+#[test]
+fn into_owned_outlives_symbol() {
+    let symbols = [
+        "foo__3BarFPC8VeryLongT1",
+        "Open__Q3_2nn2fs4FileFPCQ3_2nn2fs4Path",
+    ];
+    for symbol in symbols {
+        for input in [symbol.to_string(), compress_cpr(symbol)] {
+            let borrowed = try_demangle(&input).unwrap().name;
+            let expected = borrowed.to_string();
+            let owned: Name<'static> = borrowed.into_owned();
+            drop(input);
+            assert_eq!(owned.to_string(), expected);
+        }
+    }
+}
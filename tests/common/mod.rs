@@ -0,0 +1,5 @@
+use ghs_demangle::try_demangle;
+
+pub fn display(symbol: &str) -> String {
+    try_demangle(symbol).unwrap().name.to_string()
+}
@@ -0,0 +1,116 @@
+use ghs_demangle::{compress_cpr, decompress_cpr, try_demangle, Stage};
+
+const SYMBOLS: &[&str] = &[
+    "foo__3BarF8VeryLongT1",
+    "foo__3BarF8VeryLong8VeryLong",
+    "Open__Q3_2nn2fs4FileFPCQ3_2nn2fs4PathQ3_2nn2fs4Mode",
+    "__ct__Q2_5Jolly6JumperFRCQ2_5Jolly6Jumper",
+    "get__23Vector__tm__10_XiL_2_16FZ1Z",
+    "main",
+    "__ghs_thunk__0xfffffff8__foo__3BarF8VeryLong8VeryLong",
+];
+
+#[test]
+fn round_trip() {
+    for symbol in SYMBOLS {
+        let compressed = compress_cpr(symbol);
+        assert_eq!(decompress_cpr(&compressed).as_deref(), Ok(*symbol));
+    }
+}
+
+#[test]
+fn references_first_occurrence() {
+    assert_eq!(
+        compress_cpr("foo__3BarF8VeryLong8VeryLong"),
+        "__CPR28__foo__3BarF8VeryLongJ10J"
+    );
+    assert_eq!(
+        compress_cpr("__ct__Q2_5Jolly6JumperFRCQ2_5Jolly6Jumper"),
+        "__CPR41____ct__Q2_5JJolly6JJumperFRCQ2_J9JJ15J"
+    );
+}
+
+#[test]
+fn references_only_names() {
+    // the `3` of `vec3` starts no length-prefixed name
+    assert_eq!(
+        compress_cpr("vec3__3FooF3Foo3Foo"),
+        "__CPR19__vec3__3FooFJ6JJ6J"
+    );
+    // nor does a literal value
+    assert_eq!(
+        compress_cpr("f__FP22Vector__tm__9_XiL_2_16P22Vector__tm__9_XiL_2_16"),
+        "__CPR54__f__FP22Vector__tm__9_XiL_2_16PJ5J"
+    );
+}
+
+#[test]
+fn keeps_thunk_outside() {
+    let symbol = "__ghs_thunk__0xfffffff8__foo__3BarF8VeryLong8VeryLong";
+    let compressed = compress_cpr(symbol);
+    assert_eq!(
+        compressed,
+        "__ghs_thunk__0xfffffff8____CPR28__foo__3BarF8VeryLongJ10J"
+    );
+    assert_eq!(
+        try_demangle(&compressed).unwrap().name,
+        try_demangle(symbol).unwrap().name
+    );
+
+    // errors are at their offset in the whole symbol
+    let symbol = "__ghs_thunk__0x8____CPR9__3fooJ0J";
+    let error = decompress_cpr(symbol).unwrap_err();
+    assert_eq!((error.stage, error.offset), (Stage::Decompress, 23));
+    assert_eq!(try_demangle(symbol).unwrap_err(), error);
+}
+
+#[test]
+fn keeps_short_repeats() {
+    assert_eq!(compress_cpr("f__2abF2ab"), "__CPR10__f__2abF2ab");
+}
+
+#[test]
+fn wraps_any_input() {
+    // whether a name is long enough to compress is the caller's call
+    assert_eq!(compress_cpr("main"), "__CPR4__main");
+    assert_eq!(
+        try_demangle("__CPR4__main").unwrap().name.to_string(),
+        "main"
+    );
+}
+
+#[test]
+fn keeps_compressed_input() {
+    for symbol in [
+        "__CPR4__main",
+        "__CPR28__foo__3BarF8VeryLongJ10J",
+        "__ghs_thunk__0xfffffff8____CPR28__foo__3BarF8VeryLongJ10J",
+    ] {
+        assert_eq!(compress_cpr(symbol), symbol);
+    }
+}
+
+#[test]
+fn expands_back_references() {
+    assert_eq!(decompress_cpr("__CPR8__3fooJ0J").as_deref(), Ok("3foo3foo"));
+    assert_eq!(decompress_cpr("__CPR3__aJJb").as_deref(), Ok("aJb"));
+    assert_eq!(decompress_cpr("main").as_deref(), Ok("main"));
+}
+
+#[test]
+fn rejects_malformed_streams() {
+    let cases = [
+        ("__CPR18446744073709551615__x", 5), // absurd length
+        ("__CPR99999999999999999999__x", 5), // length overflows
+        ("__CPR9__3fooJ0J", 5),              // length mismatch
+        ("__CPR8x3foo", 6),                  // no `__` after the length
+        ("__CPR5__aJxJ", 10),                // offset is no number
+        ("__CPR8__3fooJ9J", 13),             // offset past the output
+        ("__CPR8__3fooJ0", 13),              // unclosed reference
+    ];
+    for (symbol, offset) in cases {
+        let error = decompress_cpr(symbol).unwrap_err();
+        assert_eq!((error.stage, error.offset), (Stage::Decompress, offset));
+        assert_eq!(try_demangle(symbol).unwrap_err(), error);
+    }
+}
@@ -0,0 +1,114 @@
+use ghs_demangle::{mangle_declaration, parse_declaration, try_demangle, Stage};
+
+const DECLARATIONS: &[(&str, &str)] = &[
+    (
+        "nn::fs::OpenFile(const char*, int)",
+        "OpenFile__Q2_2nn2fsFPCci",
+    ),
+    ("Foo::Foo()", "__ct__3FooFv"),
+    ("Foo::~Foo()", "__dt__3FooFv"),
+    ("Foo::foo_()", "foo___3FooFv"),
+    (
+        "Vector3::operator+(const Vector3&) const",
+        "__pl__7Vector3CFRC7Vector3",
+    ),
+    ("Flags::operator bool() const", "__opb__5FlagsCFv"),
+    ("static Foo::baz(char* name)", "baz__3FooSFPc"),
+    (
+        "f(int[4], void (*)(int), void (Foo::*)(int))",
+        "f__FPiPFi_vM3FooFi_v",
+    ),
+    // arrays and functions decay to pointers
+    ("f(int[], int[][4], void(int))", "f__FPiPA4_iPFi_v"),
+    ("static void f()", "f__Fv"),
+    (
+        "f(char* const*, const volatile int&, int (*)[4])",
+        "f__FPCPcRCViPA4_i",
+    ),
+    (
+        "h(unsigned long, long long, signed char, ...)",
+        "h__FUlLSce",
+    ),
+    (
+        "nn::Vec<int>::size() const",
+        "size__Q2_2nn12Vec__tm__2_iCFv",
+    ),
+    (
+        "f(Array<int, 16>*, Flag<true>*)",
+        "f__FP23Array__tm__10_iXiL_2_16P19Flag__tm__8_XbL_1_1",
+    ),
+    ("int Foo::gVar", "gVar__3Foo"),
+    ("Bar::f(Bar::Baz)", "f__3BarFQ2_3Bar3Baz"),
+    ("Bar::f(const Bar::Baz)", "f__3BarFQ2_3Bar3Baz"),
+    // only templates keep their return type
+    ("int (*f(char))(double)", "f__Fc"),
+    ("int (*f<int>(char))(double)", "f__tm__2_iFc_PFd_i"),
+    ("void (*signal(int, void (*)(int)))(int)", "signal__FiPFi_v"),
+    // a parameter's own cv is not part of the function type
+    ("f(const int, int* const, const int*)", "f__FiPiPCi"),
+];
+
+#[test]
+fn mangles() {
+    for (declaration, symbol) in DECLARATIONS {
+        assert_eq!(mangle_declaration(declaration).as_deref(), Ok(*symbol));
+    }
+}
+
+#[test]
+fn matches_demangled() {
+    for (declaration, symbol) in DECLARATIONS {
+        let name = try_demangle(symbol).unwrap().name;
+        assert_eq!(parse_declaration(declaration), Ok(name));
+    }
+}
+
+#[test]
+fn reports_offset() {
+    let error = parse_declaration("foo(int, )").unwrap_err();
+    assert_eq!(error.stage, Stage::Declaration);
+    assert_eq!(error.offset, 3);
+}
+
+#[test]
+fn destructor_names_its_class() {
+    assert_eq!(mangle_declaration("Foo::~Foo()").unwrap(), "__dt__3FooFv");
+    for declaration in ["Foo::~Bar()", "~Foo()"] {
+        let error = parse_declaration(declaration).unwrap_err();
+        assert_eq!(error.stage, Stage::Declaration);
+    }
+}
+
+#[test]
+fn rejects_template_declarations() {
+    for declaration in ["template<class T> f(T)", "f(class Foo)", "f(typename T)"] {
+        let error = parse_declaration(declaration).unwrap_err();
+        assert_eq!(error.stage, Stage::Declaration);
+    }
+}
+
+#[test]
+fn rejects_inner_unbounded_arrays() {
+    for declaration in ["f(int (*)[])", "f(int[4][])", "int Foo::gVar[]"] {
+        let error = parse_declaration(declaration).unwrap_err();
+        assert_eq!(error.stage, Stage::Declaration);
+    }
+}
+
+#[test]
+fn rejects_cv_without_this() {
+    for declaration in [
+        "void f() volatile",
+        "f() const",
+        "static void Foo::f() const",
+    ] {
+        let error = parse_declaration(declaration).unwrap_err();
+        assert_eq!(error.stage, Stage::Declaration);
+    }
+}
+
+#[test]
+fn rejects_rvalue_references() {
+    let error = parse_declaration("void f(int&&)").unwrap_err();
+    assert_eq!(error.stage, Stage::Declaration);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use ghs_demangle::{try_demangle, Stage};
+
+use common::display;
+
+#[test]
+fn static_members() {
+    let cases = [
+        ("foo__3Bar__SFi", "static Bar::foo(int)"),
+        ("foo__3BarSFi", "static Bar::foo(int)"),
+        ("foo__tm__2_i__3BarSFi_v", "static void Bar::foo<int>(int)"),
+        ("foo__tm__2_i__3BarFi_v", "void Bar::foo<int>(int)"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+
+    assert!(try_demangle("foo__3BarSFi").unwrap().name.is_static());
+    // both spellings are the same name
+    assert_eq!(
+        try_demangle("foo__3Bar__SFi").unwrap().name,
+        try_demangle("foo__3BarSFi").unwrap().name
+    );
+    assert!(!try_demangle("foo__3BarCFi").unwrap().name.is_static());
+
+    // `__S` without parameters is not dropped
+    let error = try_demangle("foo__3Bar__S").unwrap_err();
+    assert_eq!(
+        (error.stage, error.remaining.as_str()),
+        (Stage::TrailingInput, "__S")
+    );
+}
+
+#[test]
+fn nested_declarators() {
+    let cases = [
+        ("f__FPA4_i", "f(int (*)[4])"),
+        ("f__FA8_PFi_v", "f(void (*[8])(int))"),
+        ("f__FPFi_v", "f(void (*)(int))"),
+        ("f__Fc_PFd_i", "int (*f(char))(double)"),
+        ("f__Fi_RA4_i", "int (&f(int))[4]"),
+        ("f__Fi_Pc", "char* f(int)"),
+        ("f__FPCA4_i", "f(const int (*)[4])"),
+        ("f__FRCA4_c", "f(const char (&)[4])"),
+        ("f__FPCFv_v", "f(void (*)() const)"),
+        ("f__FPCVFv_v", "f(void (*)() const volatile)"),
+        ("f__FPCPA4_i", "f(int (* const*)[4])"),
+        ("foo__tm__2_i__3BarFi_PCc", "const char* Bar::foo<int>(int)"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+}
+
+#[test]
+fn cv_qualified_pointers() {
+    let cases = [
+        ("f__FPCc", "f(const char*)"),
+        ("f__FCPc", "f(char* const)"),
+        ("f__FPCPc", "f(char* const*)"),
+        ("f__FRCPc", "f(char* const&)"),
+        ("f__FPPc", "f(char**)"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+}
+
+#[test]
+fn pointers_to_members() {
+    let cases = [
+        ("f__FM3Fooi", "f(int Foo::*)"),
+        ("f__FM3FooPi", "f(int* Foo::*)"),
+        ("f__FM3FooA4_i", "f(int (Foo::*)[4])"),
+        ("f__FM3FooCFi_v", "f(void (Foo::*)(int) const)"),
+        ("f__FM3FooCVFv_i", "f(int (Foo::*)() const volatile)"),
+        ("f__FM12Vec__tm__2_iFv_v", "f(void (Vec<int>::*)())"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+}
+
+#[test]
+fn constructors_and_destructors() {
+    let cases = [
+        ("__ct__7MyClassFv", "MyClass::MyClass()"),
+        ("__dt__7MyClassFv", "MyClass::~MyClass()"),
+        ("__ct__12Vec__tm__2_iFv", "Vec<int>::Vec()"),
+        ("__dt__Q2_2nn6SocketFv", "nn::Socket::~Socket()"),
+        (
+            "__ct__Q2_5Jolly6JumperFRCQ2_5Jolly6Jumper",
+            "Jolly::Jumper::Jumper(const Jolly::Jumper&)",
+        ),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+}
@@ -0,0 +1,68 @@
+use ghs_demangle::{demangle, try_demangle, try_demangle_with, Mode, Name, PreprocessRule, Stage};
+
+#[test]
+fn reports_failing_stage() {
+    let cases = [
+        ("__ghs_thunk__0xzz__foo", Stage::Thunk, 15, "zz__foo"),
+        ("__ghs_thunk__0x8__", Stage::Thunk, 16, "__"),
+        ("__CPR9__3fooJ0J", Stage::Decompress, 5, "9__3fooJ0J"),
+        ("fooé__3BarFv", Stage::Preprocess, 3, "é__3BarFv"),
+        ("", Stage::Preprocess, 0, ""),
+        ("foo__3BarFT9", Stage::TrailingInput, 10, "T9"),
+        ("foo__3Bar__tm__3_T9FiPc", Stage::Grammar, 17, "T9FiPc"),
+    ];
+    for (symbol, stage, offset, remaining) in cases {
+        let error = try_demangle(symbol).unwrap_err();
+        assert_eq!(
+            (error.stage, error.offset, error.remaining.as_str()),
+            (stage, offset, remaining),
+            "{symbol}"
+        );
+    }
+
+    let error = try_demangle("foo__3Bar__tm__3_T9FiPc").unwrap_err();
+    assert_eq!(error.to_string(), "grammar failed at offset 17: \"T9FiPc\"");
+}
+
+#[test]
+fn demangle_falls_back_to_symbol() {
+    for symbol in ["foo__3BarFT9", "__ghs_thunk__0x8"] {
+        assert_eq!(
+            demangle(symbol.to_string()),
+            Name::Identifier(symbol.into())
+        );
+    }
+    assert_eq!(
+        demangle("foo__3BarFv".to_string()).to_string(),
+        "Bar::foo()"
+    );
+}
+
+#[test]
+fn strict_rejects_trailing_input() {
+    let error = try_demangle("foo__3BarFiPc<junk>").unwrap_err();
+    assert_eq!(
+        (error.stage, error.offset, error.remaining.as_str()),
+        (Stage::TrailingInput, 13, "<junk>")
+    );
+}
+
+#[test]
+fn lenient_returns_tail() {
+    let symbol = try_demangle_with("foo__3BarFiPc<junk>", Mode::Lenient).unwrap();
+    assert_eq!(symbol.name.to_string(), "Bar::foo(int, char*)");
+    assert_eq!(symbol.remaining, "<junk>");
+    assert_eq!(symbol.decision.rule, PreprocessRule::LongestPrefix);
+
+    // the parameters end at a reference to none of them
+    for reference in ["T9", "N9"] {
+        let symbol = format!("foo__3BarFi{reference}");
+        let symbol = try_demangle_with(&symbol, Mode::Lenient).unwrap();
+        assert_eq!(symbol.name.to_string(), "Bar::foo(int)");
+        assert_eq!(symbol.remaining, reference);
+    }
+
+    let symbol = try_demangle_with("foo__3BarFiPc", Mode::Lenient).unwrap();
+    assert_eq!(symbol.remaining, "");
+    assert_eq!(symbol.name, try_demangle("foo__3BarFiPc").unwrap().name);
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use ghs_demangle::{try_demangle, FunctionQualifiers, Modifier, Name};
+
+const SYMBOLS: &[&str] = &[
+    "main",
+    "foo__3BarF8VeryLongT1",
+    "Open__Q3_2nn2fs4FileFPCQ3_2nn2fs4PathQ3_2nn2fs4Mode",
+    "__ct__Q2_5Jolly6JumperFRCQ2_5Jolly6Jumper",
+    "bar__3FooCFv",
+    "baz__3FooSFPc",
+    "foo__3Bar__SFi",
+    "foo__3Bar__SCFv",
+    "foo__tm__2_i__3Bar__SFi_v",
+    "f__FUcScJdPiRiCiViuPi",
+    "__ghs_thunk__0xfffffff8__foo__3BarFv",
+    "__ghs_thunk__0x8__foo__3BarFv",
+    "__opi__3FooCFv",
+    "__pl__3VecFRC3Vec",
+    "f__FA4_iPFi_vM3FooFi_v",
+    "qux__FPFPc_v_i",
+    "f__FP23Array__tm__10_iXiL_2_16",
+    "f__FP19Neg__tm__9_XiL_2_n5",
+    "f__FP19Chr__tm__9_XcL_2_65",
+    "f__FP24Col__tm__13_X5ColorL_1_2",
+    "size__12Vec__tm__2_iCFv",
+    "f__3BarFQ2_3Bar3Baz",
+    "bar__tm__2_i__3FooFi_v",
+    "foo__tm__2_iFi_v",
+    "__op12Vec__tm__2_i__3FooCFv",
+    "f__FP17Ptr__tm__7_X4gVar",
+    "push__12Vec__tm__2_fFRCZ1Z",
+    "foo__tm__2_c__12Vec__tm__2_iFZ1ZZ1_2Z",
+    "foo___3FooFv",
+];
+
+#[test]
+fn round_trip() {
+    for symbol in SYMBOLS {
+        let name = try_demangle(symbol).unwrap().name;
+        assert_eq!(name.mangle(), *symbol);
+    }
+}
+
+#[test]
+fn same_name_where_spelled_differently() {
+    // back-references the toolchain chose differently
+    let cases = [
+        ("f__FicslfdbwPcPiT10_", "f__FicslfdbwPcPiPi"),
+        ("foo__FiT1", "foo__Fii"),
+        ("foo__FPcT1T1", "foo__FPcN21"),
+    ];
+    for (symbol, mangled) in cases {
+        let name = try_demangle(symbol).unwrap().name;
+        assert_eq!(name.mangle(), mangled);
+        assert_eq!(try_demangle(mangled).unwrap().name, name);
+    }
+}
+
+#[test]
+fn long_runs_round_trip() {
+    let function = Name::WithArguments(
+        Arc::new(Name::Identifier("f".into())),
+        vec![Name::Identifier("VeryLong".into()); 600],
+        FunctionQualifiers::default(),
+    );
+    let mangled = function.mangle();
+    assert_eq!(mangled, "f__F8VeryLongN256_1N256_1N87_1");
+    assert_eq!(try_demangle(&mangled).unwrap().name, function);
+}
+
+#[test]
+fn repeats_become_back_references() {
+    let long = Name::Identifier("VeryLong".into());
+    let function = Name::WithArguments(
+        Arc::new(Name::Identifier("f".into())),
+        vec![long.clone(), long.clone(), long, Name::BaseType('i')],
+        FunctionQualifiers::default(),
+    );
+    assert_eq!(function.mangle(), "f__F8VeryLongN21i");
+
+    // shorter spelled out
+    let function = Name::WithArguments(
+        Arc::new(Name::Identifier("f".into())),
+        vec![Name::BaseType('i'), Name::BaseType('i')],
+        FunctionQualifiers::default(),
+    );
+    assert_eq!(function.mangle(), "f__Fii");
+}
+
+#[test]
+fn constructed_name() {
+    let class = Name::Namespace(vec![
+        Name::Identifier("nn".into()),
+        Name::Identifier("Socket".into()),
+    ]);
+    let method = Name::WithArguments(
+        Arc::new(Name::InName(
+            Arc::new(Name::Identifier("Send".into())),
+            Arc::new(class),
+        )),
+        vec![
+            Name::Modifier(
+                Modifier::Pointer,
+                Arc::new(Name::Modifier(
+                    Modifier::Const,
+                    Arc::new(Name::BaseType('v')),
+                )),
+            ),
+            Name::BaseType('i'),
+        ],
+        FunctionQualifiers {
+            is_const: true,
+            ..Default::default()
+        },
+    );
+
+    let symbol = method.mangle();
+    assert_eq!(symbol, "Send__Q2_2nn6SocketCFPCvi");
+    assert_eq!(try_demangle(&symbol).unwrap().name, method);
+}
+
+#[test]
+fn free_functions_are_not_static() {
+    let function = Name::WithArguments(
+        Arc::new(Name::Identifier("f".into())),
+        vec![Name::BaseType('v')],
+        FunctionQualifiers {
+            is_static: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(function.mangle(), "f__Fv");
+}
@@ -0,0 +1,38 @@
+use ghs_demangle::try_demangle;
+
+#[test]
+fn renders_operators() {
+    let cases = [
+        (
+            "__pl__7Vector3FRC7Vector3",
+            "Vector3::operator+(const Vector3&)",
+        ),
+        ("__cl__3FooFi", "Foo::operator()(int)"),
+        ("__nt__3FooCFv", "Foo::operator!() const"),
+        ("__rm__3FooFi", "Foo::operator->*(int)"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(try_demangle(symbol).unwrap().name.to_string(), expected);
+    }
+}
+
+#[test]
+fn counts_operands() {
+    let cases = [
+        // members count their implicit `this`
+        ("__mi__7Vector3Fv", Some(1)),
+        ("__mi__7Vector3CFRC7Vector3", Some(2)),
+        ("__mi__F7Vector3T1", Some(2)),
+        ("__pl__FR3Foo", Some(1)),
+        // static members have none
+        ("__nw__3FooSFUi", Some(1)),
+        ("__ml__7Vector3SFRC7Vector3T1", Some(2)),
+        ("__ct__3FooFv", None),
+        ("__dt__3FooFv", None),
+        ("foo__3BarFi", None),
+    ];
+    for (symbol, expected) in cases {
+        let name = try_demangle(symbol).unwrap().name;
+        assert_eq!(name.operator_arity(), expected, "{symbol}");
+    }
+}
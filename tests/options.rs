@@ -0,0 +1,115 @@
+use ghs_demangle::{try_demangle, DemangleOptions};
+
+const SYMBOLS: &[&str] = &[
+    "Open__Q3_2nn2fs4FileFPCQ3_2nn2fs4Path",
+    "size__Q2_2nn12Vec__tm__2_iCFv",
+    "bar__tm__2_i__3FooSFi_PFd_i",
+];
+
+fn display(options: &DemangleOptions) -> Vec<String> {
+    SYMBOLS
+        .iter()
+        .map(|x| try_demangle(x).unwrap().name)
+        .map(|x| x.display_with(options).to_string())
+        .collect()
+}
+
+#[test]
+fn default_is_full() {
+    assert_eq!(
+        display(&DemangleOptions::default()),
+        [
+            "nn::fs::File::Open(const nn::fs::Path*)",
+            "nn::Vec<int>::size() const",
+            "static int (*Foo::bar<int>(int))(double)",
+        ]
+    );
+}
+
+#[test]
+fn no_params() {
+    let options = DemangleOptions {
+        no_params: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        display(&options),
+        [
+            "nn::fs::File::Open",
+            "nn::Vec<int>::size",
+            "int (*)(double) Foo::bar<int>",
+        ]
+    );
+}
+
+#[test]
+fn no_return_type() {
+    let options = DemangleOptions {
+        no_return_type: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        display(&options),
+        [
+            "nn::fs::File::Open(const nn::fs::Path*)",
+            "nn::Vec<int>::size() const",
+            "static Foo::bar<int>(int)",
+        ]
+    );
+}
+
+#[test]
+fn no_template_args() {
+    let options = DemangleOptions {
+        no_template_args: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        display(&options),
+        [
+            "nn::fs::File::Open(const nn::fs::Path*)",
+            "nn::Vec::size() const",
+            "static int (*Foo::bar(int))(double)",
+        ]
+    );
+}
+
+#[test]
+fn no_namespace() {
+    let options = DemangleOptions {
+        no_namespace: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        display(&options),
+        [
+            "Open(const Path*)",
+            "size() const",
+            "static int (*bar<int>(int))(double)",
+        ]
+    );
+}
+
+#[test]
+fn no_cv_qualifiers() {
+    let options = DemangleOptions {
+        no_cv_qualifiers: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        display(&options),
+        [
+            "nn::fs::File::Open(nn::fs::Path*)",
+            "nn::Vec<int>::size()",
+            "static int (*Foo::bar<int>(int))(double)",
+        ]
+    );
+}
+
+#[test]
+fn name_only() {
+    assert_eq!(
+        display(&DemangleOptions::name_only()),
+        ["Open", "size", "bar"]
+    );
+}
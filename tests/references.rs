@@ -0,0 +1,48 @@
+mod common;
+
+use ghs_demangle::{try_demangle, Stage};
+
+use common::display;
+
+#[test]
+fn multi_digit_references() {
+    assert_eq!(
+        display("f__FicslfdbwPcPiT10_"),
+        "f(int, char, short, long, float, double, bool, wchar_t, char*, int*, int*)"
+    );
+    assert_eq!(display("f__F3FooN31"), "f(Foo, Foo, Foo, Foo)");
+    assert_eq!(
+        display("f__FiN12_1"),
+        format!("f({})", ["int"; 13].join(", "))
+    );
+}
+
+#[test]
+fn deep_namespaces() {
+    assert_eq!(
+        display("f__Q_12_1a1b1c1d1e1f1g1h1i1j1k1lFv"),
+        "a::b::c::d::e::f::g::h::i::j::k::l::f()"
+    );
+}
+
+#[test]
+fn rejects_out_of_range_references() {
+    // the parameters end ahead of the reference
+    for reference in ["T0", "T2", "N22", "N257_1"] {
+        let error = try_demangle(&format!("f__Fi{reference}")).unwrap_err();
+        assert_eq!(
+            (error.stage, error.offset, error.remaining.as_str()),
+            (Stage::TrailingInput, 5, reference)
+        );
+    }
+}
+
+#[test]
+fn rejects_zero_lengths() {
+    // `T10` without its `_` leaves a `0`, which is no name
+    let error = try_demangle("f__F3FooT10").unwrap_err();
+    assert_eq!(
+        (error.stage, error.remaining.as_str()),
+        (Stage::TrailingInput, "0")
+    );
+}
@@ -0,0 +1,200 @@
+use ghs_demangle::{
+    demangle_candidates, try_demangle, try_demangle_with, DemangleOptions, Demangler, Mode, Name,
+    PreprocessRule, Score,
+};
+
+#[test]
+fn splits_at_grammatical_boundary() {
+    let cases = [
+        ("foo__12Vec__tm__2_iFv", "Vec<int>::foo()"),
+        (
+            "push__12Vec__tm__2_fFRCZ1Z",
+            "Vec<float>::push(const float&)",
+        ),
+        (
+            "call__3FooFM3FooCFi_v",
+            "Foo::call(void (Foo::*)(int) const)",
+        ),
+        ("my__func__Fv", "my__func()"),
+        ("foo___3FooFv", "Foo::foo_()"),
+        ("set___3FooFi", "Foo::set_(int)"),
+        ("value___Fi", "value_(int)"),
+        ("foo__tm__2_iFi_v", "void foo<int>(int)"),
+        (
+            "__op12Vec__tm__2_i__3FooCFv",
+            "Foo::operator Vec<int>() const",
+        ),
+    ];
+    for (symbol, expected) in cases {
+        let symbol = try_demangle(symbol).unwrap();
+        assert_eq!(symbol.decision.rule, PreprocessRule::Boundary);
+        assert!(!symbol.decision.is_ambiguous());
+        assert_eq!(symbol.name.to_string(), expected);
+    }
+}
+
+#[test]
+fn reports_ambiguity() {
+    // `x` in `Foo::Bar`, or `x__3Bar` in `Foo`
+    let symbol = try_demangle("x__3Bar__3FooFv").unwrap();
+    assert_eq!(symbol.decision.candidates, vec![1, 7]);
+    assert_eq!(symbol.decision.chosen, 1);
+    assert_eq!(symbol.name.to_string(), "Foo::Bar::x()");
+
+    // only members are static, so `foo__3Bar` can't be the function
+    let symbol = try_demangle("foo__3Bar__SFi").unwrap();
+    assert!(!symbol.decision.is_ambiguous());
+    assert_eq!(symbol.name.to_string(), "static Bar::foo(int)");
+}
+
+#[test]
+fn whole_without_boundary() {
+    for symbol in ["main", "__foo"] {
+        let decision = try_demangle(symbol).unwrap().decision;
+        assert_eq!(decision.rule, PreprocessRule::Whole);
+        assert_eq!(decision.chosen, symbol.len());
+    }
+}
+
+#[test]
+fn whole_when_nothing_follows_boundary() {
+    for symbol in ["__sti___9_main_cpp", "__std___9_main_cpp", "foo__bar"] {
+        for mode in [Mode::Strict, Mode::Lenient] {
+            let result = try_demangle_with(symbol, mode).unwrap();
+            assert_eq!(result.decision.rule, PreprocessRule::Whole);
+            assert_eq!(result.remaining, "");
+            assert_eq!(result.name.to_string(), symbol);
+        }
+    }
+}
+
+#[test]
+fn static_marker_is_not_scope() {
+    let symbol = try_demangle("foo__tm__2_i__3Bar__SFi_v").unwrap();
+    assert!(symbol.name.is_static());
+    let options = DemangleOptions {
+        no_return_type: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        symbol.name.display_with(&options).to_string(),
+        "static Bar::foo<int>(int)"
+    );
+}
+
+fn candidates(candidates: Vec<(Name, Score)>) -> Vec<(String, Score)> {
+    candidates
+        .into_iter()
+        .map(|(name, score)| (name.to_string(), score))
+        .collect()
+}
+
+#[test]
+fn ranks_candidates() {
+    // ranked like `try_demangle`, the whole symbol not among them
+    assert_eq!(
+        candidates(demangle_candidates("foo__3Bar__SFi")),
+        [("static Bar::foo(int)".to_string(), Score(100))]
+    );
+    assert_eq!(
+        candidates(demangle_candidates("foo__3BarFv")),
+        [("Bar::foo()".to_string(), Score(100))]
+    );
+    assert_eq!(
+        candidates(demangle_candidates("__ghs_thunk__0x8__foo__3BarFv")),
+        [("[thunk]: Bar::foo() (this+=8)".to_string(), Score(100))]
+    );
+
+    // a plain identifier only when nothing else parses
+    assert_eq!(
+        candidates(demangle_candidates("main")),
+        [("main".to_string(), Score(100))]
+    );
+    assert_eq!(
+        candidates(demangle_candidates("foo__bar")),
+        [("foo__bar".to_string(), Score(100))]
+    );
+    assert!(demangle_candidates("foo__3BarFiPc_x").is_empty());
+    assert!(demangle_candidates("").is_empty());
+}
+
+#[test]
+fn known_names_rank_candidates() {
+    let symbol = "x__3Bar__3FooFv";
+    assert_eq!(
+        candidates(demangle_candidates(symbol)),
+        [
+            ("Foo::Bar::x()".to_string(), Score(50)),
+            ("Foo::x__3Bar()".to_string(), Score(50)),
+        ]
+    );
+
+    // both know `Foo`; not knowing `Bar` is no evidence against the first
+    let mut demangler = Demangler::default();
+    demangler.add_known("Foo");
+    assert_eq!(
+        candidates(demangler.demangle_candidates(symbol)),
+        [
+            ("Foo::Bar::x()".to_string(), Score(50)),
+            ("Foo::x__3Bar()".to_string(), Score(50)),
+        ]
+    );
+
+    demangler.add_known("Bar");
+    assert_eq!(
+        candidates(demangler.demangle_candidates(symbol)),
+        [
+            ("Foo::Bar::x()".to_string(), Score(60)),
+            ("Foo::x__3Bar()".to_string(), Score(40)),
+        ]
+    );
+
+    let mut demangler = Demangler::default();
+    demangler.add_known("Baz");
+    assert_eq!(
+        candidates(demangler.demangle_candidates("x__9Bar__3BazFv")),
+        [
+            ("Baz::x__9Bar()".to_string(), Score(67)),
+            ("Bar__3Baz::x()".to_string(), Score(33)),
+        ]
+    );
+}
+
+#[test]
+fn known_names_pick_reading() {
+    // `x` in `Foo::Bar`, or `x__3Bar` in `Foo`
+    let symbol = "x__3Bar__3FooFv";
+    assert_eq!(
+        try_demangle(symbol).unwrap().name.to_string(),
+        "Foo::Bar::x()"
+    );
+
+    let mut demangler = Demangler::default();
+    demangler.add_known("Foo");
+    let symbol = demangler.try_demangle(symbol).unwrap();
+    assert_eq!(symbol.decision.rule, PreprocessRule::Boundary);
+    assert_eq!(symbol.name.to_string(), "Foo::Bar::x()");
+
+    // `x` in a class `Bar__3Baz`, or `x__9Bar` in `Baz`
+    let symbol = "x__9Bar__3BazFv";
+    assert_eq!(
+        try_demangle(symbol).unwrap().name.to_string(),
+        "Bar__3Baz::x()"
+    );
+    let mut demangler = Demangler::default();
+    demangler.add_known("Baz");
+    let symbol = demangler.try_demangle(symbol).unwrap();
+    assert_eq!(symbol.decision.rule, PreprocessRule::KnownNames);
+    assert_eq!(symbol.name.to_string(), "Baz::x__9Bar()");
+
+    let mut demangler = Demangler::default();
+    demangler.learn(["get__Q2_3Foo3BarFv", "foo__3Bar__SFi"]);
+    assert!(demangler.is_known("Foo") && demangler.is_known("Bar"));
+    let symbol = demangler.try_demangle("x__3Bar__3FooFv").unwrap();
+    assert_eq!(symbol.name.to_string(), "Foo::Bar::x()");
+
+    // a static member is learnt like any other
+    let mut demangler = Demangler::default();
+    demangler.learn(["foo__3Bar__SFi"]);
+    assert!(demangler.is_known("Bar"));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use ghs_demangle::{try_demangle, Name};
+
+use common::display;
+
+#[test]
+fn resolves_template_params() {
+    let cases = [
+        (
+            "push__12Vec__tm__2_fFRCZ1Z",
+            "Vec<float>::push(const float&)",
+        ),
+        // level 1 is the function's own list, level 2 its class
+        (
+            "foo__tm__2_c__12Vec__tm__2_iFZ1ZZ1_2Z",
+            "Vec<int>::foo<char>(char, int)",
+        ),
+        // declarators look through the parameter
+        ("f__tm__5_A4_iFPZ1Z", "f<int[4]>(int (*)[4])"),
+        ("f__tm__3_PcFCZ1Z", "f<char*>(char* const)"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+}
+
+#[test]
+fn keeps_params_in_tree() {
+    let name = try_demangle("push__12Vec__tm__2_fFRCZ1Z").unwrap().name;
+    let Name::WithArguments(_, args, _) = &name else {
+        panic!("not a function: {name:?}");
+    };
+    let [Name::Modifier(_, param)] = args.as_slice() else {
+        panic!("unexpected arguments: {args:?}");
+    };
+    let Name::Modifier(_, param) = &**param else {
+        panic!("not const: {param:?}");
+    };
+    match &**param {
+        Name::TemplateParam {
+            index: 1,
+            level: 1,
+            resolved: Some(ty),
+        } => assert_eq!(**ty, Name::BaseType('f')),
+        param => panic!("not a template parameter: {param:?}"),
+    }
+}
+
+#[test]
+fn keeps_placeholder_without_type() {
+    let cases = [
+        ("foo__FZ1Z", "foo(T1)"),
+        ("foo__FZ2_3Z", "foo(T2_3)"),
+        ("get__22Vector__tm__9_XiL_2_16FZ1Z", "Vector<16>::get(T1)"),
+        (
+            "at__20Pair__tm__9_iXiL_1_3FZ1ZZ2Z",
+            "Pair<int, 3>::at(int, T2)",
+        ),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(display(symbol), expected);
+    }
+}
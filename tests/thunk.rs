@@ -0,0 +1,49 @@
+use ghs_demangle::{try_demangle, Name, Stage};
+
+#[test]
+fn parses_adjustment() {
+    let cases = [
+        ("__ghs_thunk__0xfffffff8__foo__3BarFv", -8),
+        ("__ghs_thunk__0x10__foo__3BarCFv", 16),
+        ("__ghs_thunk__0xfffffffffffffff0__foo__3BarFv", -16),
+    ];
+    for (symbol, expected) in cases {
+        match try_demangle(symbol).unwrap().name {
+            Name::Thunk { adjustment, target } => {
+                assert_eq!(adjustment, expected);
+                assert_eq!(target.to_string().split('(').next(), Some("Bar::foo"));
+            }
+            name => panic!("not a thunk: {name:?}"),
+        }
+    }
+}
+
+#[test]
+fn renders_adjustment() {
+    let cases = [
+        (
+            "__ghs_thunk__0xfffffff8__foo__3BarFv",
+            "[thunk]: Bar::foo() (this-=8)",
+        ),
+        (
+            "__ghs_thunk__0x10__foo__3BarCFv",
+            "[thunk]: Bar::foo() const (this+=16)",
+        ),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(try_demangle(symbol).unwrap().name.to_string(), expected);
+    }
+}
+
+#[test]
+fn rejects_short_prefixes() {
+    let cases = [
+        ("__ghs_thunk__", 13),
+        ("__ghs_thunk__0x", 15),
+        ("__ghs_thunk__0x12345678901234567__fooFv", 15),
+    ];
+    for (symbol, offset) in cases {
+        let error = try_demangle(symbol).unwrap_err();
+        assert_eq!((error.stage, error.offset), (Stage::Thunk, offset));
+    }
+}
@@ -0,0 +1,69 @@
+use ghs_demangle::{try_demangle, Name, TemplateValue};
+
+#[test]
+fn renders_typed_literals() {
+    let cases = [
+        ("f__FP23Array__tm__10_iXiL_2_16", "f(Array<int, 16>*)"),
+        ("f__FP19Flag__tm__8_XbL_1_1", "f(Flag<true>*)"),
+        ("f__FP19Flag__tm__8_XbL_1_0", "f(Flag<false>*)"),
+        ("f__FP19Neg__tm__9_XiL_2_n5", "f(Neg<-5>*)"),
+        ("f__FP21Big__tm__10_XUlL_2_99", "f(Big<99>*)"),
+        ("f__FP19Chr__tm__9_XcL_2_65", "f(Chr<'A'>*)"),
+        ("f__FP19Chr__tm__9_XcL_2_10", "f(Chr<'\\n'>*)"),
+        ("f__FP19Chr__tm__9_XcL_2_39", "f(Chr<'\\''>*)"),
+        ("f__FP24Col__tm__13_X5ColorL_1_2", "f(Col<(Color)2>*)"),
+        ("f__FP17Ptr__tm__7_X4gVar", "f(Ptr<&gVar>*)"),
+        ("f__FP21Odd__tm__10_XfL_3_1.5", "f(Odd<(float)1.5>*)"),
+    ];
+    for (symbol, expected) in cases {
+        assert_eq!(try_demangle(symbol).unwrap().name.to_string(), expected);
+    }
+}
+
+#[test]
+fn keeps_other_signs_verbatim() {
+    let cases = [
+        ("f__FP19Neg__tm__9_XiL_2_-5", "f(Neg<(int)-5>*)"),
+        ("f__FP19Pos__tm__9_XiL_2_+5", "f(Pos<(int)+5>*)"),
+    ];
+    for (symbol, expected) in cases {
+        let name = try_demangle(symbol).unwrap().name;
+        assert_eq!(name.to_string(), expected);
+        assert_eq!(name.mangle(), symbol);
+    }
+}
+
+// arguments of the class template `f` points to
+fn template_args(symbol: &str) -> Vec<Name<'_>> {
+    let name = try_demangle(symbol).unwrap().name;
+    match name {
+        Name::WithArguments(_, args, _) => match &args[0] {
+            Name::Modifier(_, ty) => match &**ty {
+                Name::Template(_, args) => args.clone(),
+                ty => panic!("not a template: {ty:?}"),
+            },
+            arg => panic!("not a pointer: {arg:?}"),
+        },
+        name => panic!("not a function: {name:?}"),
+    }
+}
+
+#[test]
+fn decodes_by_type() {
+    assert_eq!(
+        template_args("f__FP19Flag__tm__8_XbL_1_1"),
+        [Name::ValueArgument(TemplateValue::Bool(true))]
+    );
+    assert!(matches!(
+        &template_args("f__FP19Neg__tm__9_XiL_2_n5")[..],
+        [Name::ValueArgument(TemplateValue::Integer(ty, -5))] if **ty == Name::BaseType('i')
+    ));
+    assert!(matches!(
+        &template_args("f__FP19Chr__tm__9_XcL_2_65")[..],
+        [Name::ValueArgument(TemplateValue::Char(_, 65))]
+    ));
+    assert!(matches!(
+        &template_args("f__FP17Ptr__tm__7_X4gVar")[..],
+        [Name::ValueArgument(TemplateValue::Address(x))] if x == "gVar"
+    ));
+}